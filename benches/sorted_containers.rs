@@ -1,15 +1,20 @@
+// the baseline benchmark predates the lints enforced on the crate
+#![allow(unused_imports, unused_must_use, clippy::clone_on_copy)]
+
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use rand::distributions::{Distribution, Standard};
 use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
-use sortedcontainers::sorted_containers::SortedContainers;
+use sortedcontainers::rebalance_policy::AdaptiveLoadFactor;
+use sortedcontainers::sorted_containers::{OrderType, SortedContainers};
 
 criterion_main!(benches);
 
 criterion_group! {
     name = benches;
     config = Criterion::default();
-    targets = sorted_containers_benchmark
+    targets = sorted_containers_benchmark, bulk_load_benchmark, positional_index_benchmark,
+        rebalance_policy_benchmark
 }
 
 fn sorted_containers_benchmark(c: &mut Criterion) {
@@ -38,6 +43,123 @@ fn sorted_containers_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
+fn bulk_load_benchmark(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let mut group = c.benchmark_group("bulk load sorted containers up to 1_000_000");
+    for len in (100_000..=1_000_000).step_by(100_000) {
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::new("random", len), &len, |b, &len| {
+            let mut input: Vec<i32> = (-len..len).collect();
+            input.shuffle(&mut rng);
+            b.iter(|| input.iter().copied().collect::<SortedContainers<i32>>())
+        });
+        group.bench_with_input(BenchmarkId::new("sorted", len), &len, |b, &len| {
+            let input: Vec<i32> = (-len..len).collect();
+            b.iter(|| SortedContainers::from_sorted_vec(OrderType::Asc, input.clone()))
+        });
+    }
+    group.finish();
+}
+
+fn positional_index_benchmark(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let mut group = c.benchmark_group("positional index up to 10_000_000");
+    for len in [1_000_000, 5_000_000, 10_000_000] {
+        let vec =
+            SortedContainers::from_sorted_vec(OrderType::Asc, (0..len).map(|i| i * 2).collect());
+        let positions: Vec<usize> = (0..10_000).map(|_| rng.gen_range(0..vec.len())).collect();
+        let values: Vec<i32> = (0..10_000).map(|_| rng.gen_range(0..len) * 2 + 1).collect();
+        group.throughput(Throughput::Elements(positions.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("index", len),
+            &positions,
+            |b, positions| b.iter(|| positions.iter().map(|&position| vec[position]).sum::<i32>()),
+        );
+        let mut vec = vec;
+        group.bench_with_input(
+            BenchmarkId::new("insert and remove", len),
+            &values,
+            |b, values| {
+                b.iter(|| {
+                    for value in values {
+                        let _ = vec.insert(*value);
+                    }
+                    for value in values {
+                        vec.remove(value);
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn rebalance_policy_benchmark(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let mut group = c.benchmark_group("fixed and adaptive load factor");
+    let adaptive = || {
+        SortedContainers::builder()
+            .rebalance_policy(AdaptiveLoadFactor::new())
+            .build()
+    };
+    for len in [100, 10_000, 1_000_000, 10_000_000] {
+        let mut input: Vec<i32> = (0..len).collect();
+        input.shuffle(&mut rng);
+        group.throughput(Throughput::Elements(len as u64));
+        if len <= 1_000_000 {
+            group.bench_with_input(BenchmarkId::new("fixed insert", len), &input, |b, input| {
+                b.iter(|| fill(SortedContainers::default(), input))
+            });
+            group.bench_with_input(
+                BenchmarkId::new("adaptive insert", len),
+                &input,
+                |b, input| b.iter(|| fill(adaptive(), input)),
+            );
+        }
+        // lookups, insertions and removals on a collection of steady length
+        let positions: Vec<usize> = (0..10_000)
+            .map(|_| rng.gen_range(0..len as usize))
+            .collect();
+        let values: Vec<i32> = (0..10_000).map(|_| rng.gen_range(len..2 * len)).collect();
+        group.throughput(Throughput::Elements(positions.len() as u64));
+        for (name, vec) in [
+            ("fixed", fill(SortedContainers::default(), &input)),
+            ("adaptive", fill(adaptive(), &input)),
+        ] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name} index"), len),
+                &positions,
+                |b, positions| {
+                    b.iter(|| positions.iter().map(|&position| vec[position]).sum::<i32>())
+                },
+            );
+            let mut vec = vec;
+            group.bench_with_input(
+                BenchmarkId::new(format!("{name} insert and remove"), len),
+                &values,
+                |b, values| {
+                    b.iter(|| {
+                        for value in values {
+                            let _ = vec.insert(*value);
+                        }
+                        for value in values {
+                            vec.remove(value);
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn fill(mut vec: SortedContainers<i32>, input: &[i32]) -> SortedContainers<i32> {
+    for el in input {
+        let _ = vec.insert(*el);
+    }
+    vec
+}
+
 fn insert_in_sorted_containers(input: &Vec<i32>) {
     let mut vec: SortedContainers<i32> = SortedContainers::default();
     for el in input {
@@ -0,0 +1,243 @@
+use crate::errors::SortedContainersError;
+use crate::sorted_container_iter::SortedContainerIter;
+use crate::sorted_containers::{OrderType, SortedContainers};
+use std::cmp::Ordering;
+use std::ops::Index;
+
+/// The end of a `BoundedSortedContainers<T>` from which elements are evicted when it's full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionSide {
+    /// the first element in the order of the collection is evicted
+    First,
+    /// the last element in the order of the collection is evicted
+    #[default]
+    Last,
+}
+/// A sorted collection holding at most `capacity` elements, evicting the element at one end
+/// of the order when an element is inserted in the full collection.
+///
+/// With the default eviction side the collection keeps the first `capacity` elements of the
+/// order ever inserted, e.g. the lowest ones for an ascending collection.
+pub struct BoundedSortedContainers<T> {
+    sorted: SortedContainers<T>,
+    capacity: usize,
+    eviction_side: EvictionSide,
+}
+impl<T: Ord> BoundedSortedContainers<T> {
+    /// Constructs a new empty `BoundedSortedContainers<T>` with the specified order type,
+    /// evicting the last element when full.
+    ///
+    /// # Examples
+    /// let mut leaderboard = BoundedSortedContainers::new(OrderType::Desc, 2);
+    /// leaderboard.insert(10);
+    /// leaderboard.insert(30);
+    /// assert_eq!(leaderboard.insert(20).unwrap(), Some(10));
+    /// // the leaderboard keeps the best two scores: [30, 20]
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new(order_type: OrderType, capacity: usize) -> Self {
+        BoundedSortedContainers::from_sorted_containers(
+            SortedContainers::new(order_type),
+            capacity,
+            EvictionSide::Last,
+        )
+    }
+    /// Constructs a new empty multiset `BoundedSortedContainers<T>` with the specified order
+    /// type, evicting the last element when full. See `SortedContainers::new_multiset`.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new_multiset(order_type: OrderType, capacity: usize) -> Self {
+        BoundedSortedContainers::from_sorted_containers(
+            SortedContainers::new_multiset(order_type),
+            capacity,
+            EvictionSide::Last,
+        )
+    }
+    /// Constructs a new `BoundedSortedContainers<T>` from the collection in input, keeping its
+    /// order and duplicate policy and evicting from the specified side the elements exceeding
+    /// the capacity.
+    ///
+    /// Panics if the capacity is 0.
+    pub fn from_sorted_containers(
+        mut sorted: SortedContainers<T>,
+        capacity: usize,
+        eviction_side: EvictionSide,
+    ) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        if sorted.len() > capacity {
+            let excess = sorted.len() - capacity;
+            match eviction_side {
+                EvictionSide::First => sorted.remove_range(..excess),
+                EvictionSide::Last => sorted.remove_range(capacity..),
+            };
+        }
+        BoundedSortedContainers {
+            sorted,
+            capacity,
+            eviction_side,
+        }
+    }
+    /// Returns the maximum number of elements of the collection
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Returns the side from which elements are evicted
+    pub fn eviction_side(&self) -> EvictionSide {
+        self.eviction_side
+    }
+    /// Returns the number of elements in the collection
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+    /// Returns `true` if the collection contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+    /// Returns `true` if the collection holds `capacity` elements
+    pub fn is_full(&self) -> bool {
+        self.sorted.len() == self.capacity
+    }
+    /// Insert an element inside the collection, returning the evicted element if the collection
+    /// was full.
+    ///
+    /// If the collection is full and the element would be evicted right away, it's returned
+    /// without touching the collection, so only one comparison is performed.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N)
+    /// If the element already exists and the collection is not a multiset, an error is
+    /// returned and nothing is evicted.
+    pub fn insert(&mut self, value: T) -> Result<Option<T>, SortedContainersError<T>> {
+        if !self.is_full() {
+            return self.sorted.insert(value).map(|_| None);
+        }
+        let (end, evicted_ordering) = match self.eviction_side {
+            EvictionSide::First => (self.sorted.first().unwrap(), Ordering::Less),
+            EvictionSide::Last => (self.sorted.last().unwrap(), Ordering::Greater),
+        };
+        let ordering = self.sorted.compare(&value, end);
+        // equal elements are inserted after the ones already stored, so in a multiset an
+        // element equal to the last one would be the last
+        if ordering == evicted_ordering
+            || (ordering == Ordering::Equal
+                && self.sorted.is_multiset()
+                && self.eviction_side == EvictionSide::Last)
+        {
+            return Ok(Some(value));
+        }
+        self.sorted.insert(value)?;
+        Ok(match self.eviction_side {
+            EvictionSide::First => self.sorted.pop_first(),
+            EvictionSide::Last => self.sorted.pop_last(),
+        })
+    }
+    /// Remove the element equal to the one in input and return it.
+    /// In a multiset the first equal element is removed.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        self.sorted.remove(value)
+    }
+    /// Returns the position of the element in input, if present.
+    pub fn find(&self, value: &T) -> Option<usize> {
+        self.sorted.find(value)
+    }
+    /// Returns the first element of the collection.
+    pub fn first(&self) -> Option<&T> {
+        self.sorted.first()
+    }
+    /// Returns the last element of the collection.
+    pub fn last(&self) -> Option<&T> {
+        self.sorted.last()
+    }
+    /// Remove all the elements from the collection
+    pub fn clear(&mut self) {
+        self.sorted.clear();
+    }
+    /// Returns the elements of the collection.
+    pub fn sorted(&self) -> &SortedContainers<T> {
+        &self.sorted
+    }
+    /// Consume the collection, returning its elements.
+    pub fn into_sorted(self) -> SortedContainers<T> {
+        self.sorted
+    }
+    /// Returns an iterator over the elements of the collection.
+    pub fn iter(&self) -> SortedContainerIter<'_, T> {
+        self.sorted.iter()
+    }
+}
+impl<T: Ord> Index<usize> for BoundedSortedContainers<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.sorted[index]
+    }
+}
+impl<'a, T: Ord> IntoIterator for &'a BoundedSortedContainers<T> {
+    type Item = &'a T;
+
+    type IntoIter = SortedContainerIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::bounded_sorted_containers::{BoundedSortedContainers, EvictionSide};
+    use crate::sorted_containers::{OrderType, SortedContainers};
+    use rand::prelude::SliceRandom;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_insert_evicts_last() {
+        let mut leaderboard = BoundedSortedContainers::new(OrderType::Desc, 3);
+        assert_eq!(leaderboard.insert(10).unwrap(), None);
+        assert_eq!(leaderboard.insert(30).unwrap(), None);
+        assert!(!leaderboard.is_full());
+        assert_eq!(leaderboard.insert(20).unwrap(), None);
+        assert!(leaderboard.is_full());
+        assert_eq!(leaderboard.insert(25).unwrap(), Some(10));
+        assert_eq!(leaderboard.insert(5).unwrap(), Some(5));
+        assert!(leaderboard.insert(25).is_err());
+        assert_eq!(leaderboard.insert(40).unwrap(), Some(20));
+        assert_eq!(
+            leaderboard.iter().copied().collect::<Vec<i32>>(),
+            [40, 30, 25]
+        );
+        assert_eq!(leaderboard[2], 25);
+        assert_eq!(leaderboard.len(), 3);
+    }
+    #[test]
+    fn test_insert_evicts_first() {
+        let sorted = SortedContainers::from_sorted_vec(OrderType::Asc, vec![1, 2, 3, 4, 5]);
+        let mut bounded =
+            BoundedSortedContainers::from_sorted_containers(sorted, 3, EvictionSide::First);
+        assert_eq!(bounded.iter().copied().collect::<Vec<i32>>(), [3, 4, 5]);
+        assert_eq!(bounded.insert(0).unwrap(), Some(0));
+        assert_eq!(bounded.insert(10).unwrap(), Some(3));
+        assert!(bounded.insert(4).is_err());
+        assert_eq!(bounded.first(), Some(&4));
+        assert_eq!(bounded.last(), Some(&10));
+        assert_eq!(bounded.eviction_side(), EvictionSide::First);
+    }
+    #[test]
+    fn test_multiset_top_k() {
+        let capacity = 10_000;
+        let mut scores: Vec<u32> = (0..50_000).map(|score| score / 3).collect();
+        scores.shuffle(&mut thread_rng());
+        let mut leaderboard = BoundedSortedContainers::new_multiset(OrderType::Desc, capacity);
+        let mut evicted = 0;
+        for score in scores.iter() {
+            if leaderboard.insert(*score).unwrap().is_some() {
+                evicted += 1;
+            }
+        }
+        assert_eq!(evicted, scores.len() - capacity);
+        scores.sort_by(|a, b| b.cmp(a));
+        scores.truncate(capacity);
+        assert!(leaderboard.iter().eq(scores.iter()));
+        assert_eq!(leaderboard.remove(&scores[0]), Some(scores[0]));
+        assert_eq!(leaderboard.find(&scores[0]), Some(0));
+        leaderboard.clear();
+        assert!(leaderboard.is_empty());
+        assert_eq!(leaderboard.capacity(), capacity);
+    }
+}
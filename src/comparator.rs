@@ -0,0 +1,45 @@
+use crate::sorted_containers::OrderType;
+use std::cmp::Ordering;
+
+/// Defines the order used by a sorted collection to store its elements.
+///
+/// Every closure `Fn(&T, &T) -> Ordering` is a comparator, so a collection can be sorted by
+/// an order chosen at runtime (case-insensitive strings, collation orders, multi-field orders
+/// with mixed directions...). `OrderType::Asc` and `OrderType::Desc` are the two built-in
+/// comparators relying on `T::cmp`.
+///
+/// Two elements are considered the same element when the comparator returns `Ordering::Equal`.
+///
+/// A panicking comparator doesn't corrupt the collection: the insertions, updates, removals and
+/// entries compare the elements before moving any of them, so the collection is left as it was
+/// before the operation that panicked, and no element is leaked or dropped twice.
+pub trait Comparator<T>: Send + Sync {
+    /// Compare two elements, returning `Ordering::Less` if `a` must be stored before `b`.
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+    /// Returns `true` if the comparator stores the elements in the reverse of their value order,
+    /// like `OrderType::Desc`, so that the queries defined on values (`floor`, `ceiling`, the
+    /// quantiles...) can walk the collection backwards. By default the order of the comparator
+    /// is the value order.
+    fn is_descending(&self) -> bool {
+        false
+    }
+}
+impl<T, F> Comparator<T> for F
+where
+    F: Fn(&T, &T) -> Ordering + Send + Sync,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+impl<T: Ord> Comparator<T> for OrderType {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        match self {
+            OrderType::Asc => a.cmp(b),
+            OrderType::Desc => b.cmp(a),
+        }
+    }
+    fn is_descending(&self) -> bool {
+        *self == OrderType::Desc
+    }
+}
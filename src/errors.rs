@@ -1,7 +1,7 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
-pub enum SortedContainersError<T: Ord + Clone> {
+pub enum SortedContainersError<T> {
     #[error("element `{0}` already exist")]
     ElementAlreadyExist(T),
     #[error("element `{0}` not found")]
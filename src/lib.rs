@@ -3,6 +3,17 @@
 //! in a sorted collection. For any other use cases rely on the battle tested BinaryHeap and BTreeMap data structure.
 extern crate core;
 
+pub mod bounded_sorted_containers;
+pub mod comparator;
 mod errors;
+mod positional_index;
+pub mod rebalance_policy;
+pub mod sorted_container_entry;
 pub mod sorted_container_iter;
 pub mod sorted_containers;
+pub mod sorted_containers_builder;
+pub mod sorted_key_containers;
+pub mod sorted_map;
+pub mod sorted_map_iter;
+pub mod statistics;
+pub mod windowed_sorted_containers;
@@ -0,0 +1,135 @@
+/// Positional index of a sorted collection, mapping the position of an element to the
+/// `(sub-vector, offset)` pair where it's stored and vice versa.
+///
+/// The index is a Fenwick tree over the lengths of the sub-vectors: `tree[i]` stores the total
+/// length of the sub-vectors between `i & (i + 1)` and `i`, both included. Positions are then
+/// converted and lengths updated in O(log(M)), while the tree is built from scratch in O(M)
+/// whenever sub-vectors are splitted or merged.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PositionalIndex {
+    tree: Vec<usize>,
+}
+impl PositionalIndex {
+    /// build the index of the sub-vectors in input
+    /// Complexity is O(M)
+    pub(crate) fn build<T>(&mut self, data: &[Vec<T>]) {
+        self.tree.clear();
+        self.tree.extend(data.iter().map(|values| values.len()));
+        for i in 0..self.tree.len() {
+            let parent = i | (i + 1);
+            if parent < self.tree.len() {
+                self.tree[parent] += self.tree[i];
+            }
+        }
+    }
+    /// Returns the number of indexed sub-vectors
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.tree.len()
+    }
+    /// remove all the indexed sub-vectors
+    pub(crate) fn clear(&mut self) {
+        self.tree.clear();
+    }
+    /// increase by `count` the length of the sub-vector at the position in input
+    /// Complexity is O(log(M))
+    pub(crate) fn increase(&mut self, mut pos: usize, count: usize) {
+        while pos < self.tree.len() {
+            self.tree[pos] += count;
+            pos |= pos + 1;
+        }
+    }
+    /// decrease by `count` the length of the sub-vector at the position in input
+    /// Complexity is O(log(M))
+    pub(crate) fn decrease(&mut self, mut pos: usize, count: usize) {
+        while pos < self.tree.len() {
+            self.tree[pos] -= count;
+            pos |= pos + 1;
+        }
+    }
+    /// given a position in the form `(usize, usize)`, returns an index
+    /// Complexity is O(log(M))
+    #[inline]
+    pub(crate) fn index_from_tuple(&self, (pos, idx): (usize, usize)) -> usize {
+        // sum the lengths of the sub-vectors preceding `pos`
+        let mut index = idx;
+        let mut end = pos;
+        while end > 0 {
+            index += self.tree[end - 1];
+            end &= end - 1;
+        }
+        index
+    }
+    /// given an index lower than the total length, returns its position in the form
+    /// `(usize, usize)`
+    /// Complexity is O(log(M))
+    #[inline]
+    pub(crate) fn tuple_from_index(&self, mut index: usize) -> (usize, usize) {
+        // descend the tree skipping the sub-vectors that end before the index
+        let mut pos = 0;
+        let mut step = match self.tree.len() {
+            0 => 0,
+            len => 1 << len.ilog2(),
+        };
+        while step > 0 {
+            if pos + step <= self.tree.len() && self.tree[pos + step - 1] <= index {
+                index -= self.tree[pos + step - 1];
+                pos += step;
+            }
+            step >>= 1;
+        }
+        (pos, index)
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::positional_index::PositionalIndex;
+    use rand::{thread_rng, Rng};
+
+    fn check_index(index: &PositionalIndex, data: &[Vec<usize>]) {
+        let mut offset = 0;
+        for (pos, values) in data.iter().enumerate() {
+            assert_eq!(index.index_from_tuple((pos, 0)), offset);
+            for idx in 0..values.len() {
+                assert_eq!(index.tuple_from_index(offset + idx), (pos, idx));
+            }
+            offset += values.len();
+        }
+        assert_eq!(index.index_from_tuple((data.len(), 0)), offset);
+    }
+    #[test]
+    fn test_build() {
+        let mut rng = thread_rng();
+        let mut index = PositionalIndex::default();
+        for sub_vectors in [1, 2, 3, 7, 8, 9, 100] {
+            let data: Vec<Vec<usize>> = (0..sub_vectors)
+                .map(|_| vec![0; rng.gen_range(1..50)])
+                .collect();
+            index.build(&data);
+            assert_eq!(index.len(), sub_vectors);
+            check_index(&index, &data);
+        }
+        index.clear();
+        assert_eq!(index.len(), 0);
+    }
+    #[test]
+    fn test_increase_and_decrease() {
+        let mut rng = thread_rng();
+        let mut data: Vec<Vec<usize>> = (0..37).map(|_| vec![0; 20]).collect();
+        let mut index = PositionalIndex::default();
+        index.build(&data);
+        for _ in 0..1_000 {
+            let pos = rng.gen_range(0..data.len());
+            let count = rng.gen_range(1..10);
+            if rng.gen_bool(0.5) {
+                data[pos].extend(vec![0; count]);
+                index.increase(pos, count);
+            } else if data[pos].len() > count {
+                let len = data[pos].len();
+                data[pos].truncate(len - count);
+                index.decrease(pos, count);
+            }
+        }
+        check_index(&index, &data);
+    }
+}
@@ -0,0 +1,321 @@
+/// Describes a sub-vector of a sorted collection when deciding whether it must be rebalanced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RebalanceContext {
+    /// position of the sub-vector
+    pub pos: usize,
+    /// number of elements stored inside the sub-vector
+    pub len: usize,
+    /// number of elements stored inside the collection
+    pub total_len: usize,
+    /// number of sub-vectors of the collection
+    pub depth: usize,
+    /// number of elements stored inside the previous sub-vector, if any
+    pub previous_len: Option<usize>,
+    /// number of elements stored inside the next sub-vector, if any
+    pub next_len: Option<usize>,
+}
+/// Decides when the sub-vectors of a sorted collection are splitted and merged.
+///
+/// A sub-vector is splitted in two halves when `should_expand` returns `true` after an element
+/// is inserted into it, while it's merged with one of its neighbours when `should_shrink`
+/// returns `true` after an element is removed from it. These methods take `&mut self`, so a
+/// policy can keep statistics about the collection it's attached to, while `load_factor` takes
+/// `&self`, since it's only asked for the length of the sub-vectors built at once.
+///
+/// Every policy implementing `Clone` can be used: the policy is cloned whenever a new collection
+/// is derived from an existing one, e.g. by `split_off` or the set operations.
+pub trait RebalancePolicy: CloneRebalancePolicy + Send + Sync {
+    /// Returns `true` if the sub-vector described by the context must be splitted in two.
+    fn should_expand(&mut self, context: &RebalanceContext) -> bool;
+    /// Returns `true` if the sub-vector described by the context must be merged with one of its
+    /// neighbours.
+    fn should_shrink(&mut self, context: &RebalanceContext) -> bool;
+    /// Returns the target length of the sub-vectors built at once, when `total_len` elements
+    /// are bulk loaded or when a batch merged into a sub-vector makes it splitted.
+    ///
+    /// It must not change the state of the policy, and it should be consistent with
+    /// `should_expand`: the sub-vectors of the target length must not be splitted.
+    fn load_factor(&self, total_len: usize) -> usize;
+    /// Returns `true` if all the sub-vectors must be rebuilt with the length returned by
+    /// `load_factor`, asked whenever the length of the collection changes.
+    ///
+    /// Rebuilding costs O(N), so a policy should ask for it only when its target length changes
+    /// significantly. By default the sub-vectors are never rebuilt.
+    fn should_rebuild(&mut self, _total_len: usize, _depth: usize) -> bool {
+        false
+    }
+}
+/// Clones a boxed `RebalancePolicy`, implemented for every policy implementing `Clone`.
+pub trait CloneRebalancePolicy {
+    fn clone_box(&self) -> Box<dyn RebalancePolicy>;
+}
+impl<P: RebalancePolicy + Clone + 'static> CloneRebalancePolicy for P {
+    fn clone_box(&self) -> Box<dyn RebalancePolicy> {
+        Box::new(self.clone())
+    }
+}
+impl Clone for Box<dyn RebalancePolicy> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+/// The default policy: sub-vectors are kept between half and twice the load factor, like the
+/// `_load` of Python sortedcontainers.
+///
+/// A sub-vector is splitted when it stores more than `2 * load_factor` elements and merged when
+/// it stores less than `load_factor / 2` elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedLoadFactor {
+    load_factor: usize,
+}
+impl FixedLoadFactor {
+    /// Constructs a new policy with the load factor in input.
+    ///
+    /// Panics if the load factor is 0.
+    pub fn new(load_factor: usize) -> FixedLoadFactor {
+        assert!(load_factor > 0, "load factor must be greater than 0");
+        FixedLoadFactor { load_factor }
+    }
+}
+impl Default for FixedLoadFactor {
+    /// Sub-vectors are splitted above 2000 elements and merged below 500 elements.
+    fn default() -> Self {
+        FixedLoadFactor::new(1000)
+    }
+}
+impl RebalancePolicy for FixedLoadFactor {
+    fn should_expand(&mut self, context: &RebalanceContext) -> bool {
+        context.len > 2 * self.load_factor
+    }
+    fn should_shrink(&mut self, context: &RebalanceContext) -> bool {
+        context.len < self.load_factor / 2
+    }
+    fn load_factor(&self, _total_len: usize) -> usize {
+        self.load_factor
+    }
+}
+/// A policy delegating its decisions to two closures, receiving the context of the sub-vector.
+///
+/// The load factor is half of the smallest length for which `expand_strategy` returns `true`,
+/// found asking it about a single sub-vector storing all the elements.
+///
+/// # Examples
+/// let policy = StrategyPolicy::new(
+///     move |context: &RebalanceContext| context.len > max_len,
+///     move |context: &RebalanceContext| context.len < min_len,
+/// );
+#[derive(Clone, Copy, Debug)]
+pub struct StrategyPolicy<E, S> {
+    expand_strategy: E,
+    shrink_strategy: S,
+}
+impl<E, S> StrategyPolicy<E, S>
+where
+    E: Fn(&RebalanceContext) -> bool + Clone + Send + Sync + 'static,
+    S: Fn(&RebalanceContext) -> bool + Clone + Send + Sync + 'static,
+{
+    /// Constructs a new policy splitting the sub-vectors when `expand_strategy` returns `true`
+    /// and merging them when `shrink_strategy` returns `true`.
+    pub fn new(expand_strategy: E, shrink_strategy: S) -> StrategyPolicy<E, S> {
+        StrategyPolicy {
+            expand_strategy,
+            shrink_strategy,
+        }
+    }
+}
+impl<E, S> RebalancePolicy for StrategyPolicy<E, S>
+where
+    E: Fn(&RebalanceContext) -> bool + Clone + Send + Sync + 'static,
+    S: Fn(&RebalanceContext) -> bool + Clone + Send + Sync + 'static,
+{
+    fn should_expand(&mut self, context: &RebalanceContext) -> bool {
+        (self.expand_strategy)(context)
+    }
+    fn should_shrink(&mut self, context: &RebalanceContext) -> bool {
+        (self.shrink_strategy)(context)
+    }
+    fn load_factor(&self, total_len: usize) -> usize {
+        let should_expand = |len: usize| {
+            (self.expand_strategy)(&RebalanceContext {
+                pos: 0,
+                len,
+                total_len,
+                depth: 1,
+                previous_len: None,
+                next_len: None,
+            })
+        };
+        let mut high = 1;
+        while !should_expand(high) {
+            if high >= total_len {
+                // a single sub-vector can store all the elements
+                return total_len.max(1);
+            }
+            high *= 2;
+        }
+        let mut low = high / 2;
+        while low + 1 < high {
+            let middle = (low + high) >> 1;
+            if should_expand(middle) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        (high / 2).max(1)
+    }
+}
+/// A policy growing the target length of the sub-vectors with the length of the collection,
+/// by default with its square root, so that both the sub-vectors and their number grow slowly.
+///
+/// Sub-vectors are splitted above twice the target length and merged below half of it, like
+/// `FixedLoadFactor`, with the target computed from the current length of the collection.
+/// Whenever the length has doubled or halved since the last check, all the sub-vectors are
+/// rebuilt if their average length has drifted below half or above twice the target: the O(N)
+/// rebuild happens at most once every N/2 changes, so its amortized cost is O(1) while
+/// `depth()` stays close to `total_len / target` over the lifetime of the collection.
+///
+/// # Examples
+/// let sorted_containers: SortedContainers<i32> = SortedContainers::builder()
+///     .rebalance_policy(AdaptiveLoadFactor::new())
+///     .build();
+/// // a target growing with the fourth root of the length, between 64 and 4096 elements
+/// let policy = AdaptiveLoadFactor::with_curve(|total_len: usize| 16 * total_len.isqrt().isqrt())
+///     .bounds(64, 4096);
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveLoadFactor<C = fn(usize) -> usize> {
+    curve: C,
+    min_load_factor: usize,
+    max_load_factor: usize,
+    checked_len: usize,
+}
+impl AdaptiveLoadFactor {
+    /// Constructs a new policy with a target of `4 * sqrt(total_len)` elements, between 32 and
+    /// 65536 elements: about 1250 elements for 100k elements and 40k for 100M.
+    pub fn new() -> AdaptiveLoadFactor {
+        AdaptiveLoadFactor::with_curve(|total_len| 4 * total_len.isqrt())
+    }
+}
+impl Default for AdaptiveLoadFactor {
+    fn default() -> Self {
+        AdaptiveLoadFactor::new()
+    }
+}
+impl<C> AdaptiveLoadFactor<C>
+where
+    C: Fn(usize) -> usize + Clone + Send + Sync + 'static,
+{
+    /// Constructs a new policy whose target length is returned by the curve in input, given the
+    /// length of the collection. The target is clamped between 32 and 65536 elements, see
+    /// `bounds`. The curve should be non-decreasing.
+    pub fn with_curve(curve: C) -> AdaptiveLoadFactor<C> {
+        AdaptiveLoadFactor {
+            curve,
+            min_load_factor: 32,
+            max_load_factor: 1 << 16,
+            checked_len: 0,
+        }
+    }
+    /// Clamp the target length between the minimum and the maximum in input, both included.
+    ///
+    /// Panics if the minimum is 0 or greater than the maximum.
+    pub fn bounds(mut self, min_load_factor: usize, max_load_factor: usize) -> Self {
+        assert!(min_load_factor > 0, "load factor must be greater than 0");
+        assert!(
+            min_load_factor <= max_load_factor,
+            "minimum load factor is greater than the maximum one"
+        );
+        self.min_load_factor = min_load_factor;
+        self.max_load_factor = max_load_factor;
+        self
+    }
+    /// returns the target length for a collection of the length in input
+    fn target(&self, total_len: usize) -> usize {
+        (self.curve)(total_len).clamp(self.min_load_factor, self.max_load_factor)
+    }
+}
+impl<C> RebalancePolicy for AdaptiveLoadFactor<C>
+where
+    C: Fn(usize) -> usize + Clone + Send + Sync + 'static,
+{
+    fn should_expand(&mut self, context: &RebalanceContext) -> bool {
+        context.len > 2 * self.target(context.total_len)
+    }
+    fn should_shrink(&mut self, context: &RebalanceContext) -> bool {
+        context.len < self.target(context.total_len) / 2
+    }
+    fn load_factor(&self, total_len: usize) -> usize {
+        self.target(total_len)
+    }
+    fn should_rebuild(&mut self, total_len: usize, depth: usize) -> bool {
+        // the sub-vectors are checked only when the length has doubled or halved since the last
+        // check
+        if total_len / 2 <= self.checked_len && total_len >= self.checked_len / 2 {
+            return false;
+        }
+        self.checked_len = total_len;
+        let target = self.target(total_len);
+        (depth > 1 && 2 * total_len < depth * target) || total_len > 2 * depth * target
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::rebalance_policy::{
+        AdaptiveLoadFactor, FixedLoadFactor, RebalanceContext, RebalancePolicy, StrategyPolicy,
+    };
+
+    #[test]
+    fn test_strategy_load_factor() {
+        let policy = FixedLoadFactor::new(100);
+        assert_eq!(policy.load_factor(10_000), 100);
+        // half of the smallest length splitted by the policy
+        let mut policy = StrategyPolicy::new(
+            |context: &RebalanceContext| context.len > 300,
+            |_: &RebalanceContext| false,
+        );
+        assert_eq!(policy.load_factor(10_000), 150);
+        assert_eq!(policy.load_factor(100), 100);
+        assert!(!policy.should_rebuild(10_000, 1));
+    }
+    #[test]
+    fn test_adaptive_load_factor() {
+        let context = |len: usize, total_len: usize| RebalanceContext {
+            pos: 0,
+            len,
+            total_len,
+            depth: total_len.div_ceil(len),
+            previous_len: None,
+            next_len: Some(len),
+        };
+        let mut policy = AdaptiveLoadFactor::new();
+        assert_eq!(policy.load_factor(100), 40);
+        assert_eq!(policy.load_factor(10), 32);
+        assert_eq!(policy.load_factor(1_000_000), 4_000);
+        assert_eq!(policy.load_factor(u32::MAX as usize * 16), 1 << 16);
+        // the sub-vectors are kept between half and twice the target of the current length
+        assert!(policy.should_expand(&context(801, 10_000)));
+        assert!(!policy.should_expand(&context(800, 10_000)));
+        assert!(policy.should_shrink(&context(199, 10_000)));
+        assert!(!policy.should_shrink(&context(200, 10_000)));
+        assert!(!policy.should_expand(&context(801, 1_000_000)));
+        // the sub-vectors are checked only when the length doubles or halves
+        assert!(policy.should_rebuild(10_000, 1_000));
+        assert!(!policy.should_rebuild(19_000, 1_000));
+        assert!(!policy.should_rebuild(5_000, 1_000));
+        // an average length of 750 elements is close enough to the target of 692 elements
+        assert!(!policy.should_rebuild(30_000, 40));
+        assert!(policy.should_rebuild(150_000, 40));
+        assert!(policy.should_rebuild(1_000, 40));
+        let mut policy = AdaptiveLoadFactor::with_curve(|total_len| total_len / 10).bounds(8, 64);
+        assert_eq!(policy.load_factor(0), 8);
+        assert_eq!(policy.load_factor(200), 20);
+        assert_eq!(policy.load_factor(10_000), 64);
+        assert!(!policy.should_rebuild(1_000_000, 15_625));
+        assert!(policy.should_rebuild(10_000_000, 15_625));
+    }
+    #[test]
+    #[should_panic(expected = "minimum load factor is greater than the maximum one")]
+    fn test_adaptive_load_factor_wrong_bounds() {
+        AdaptiveLoadFactor::new().bounds(100, 10);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::sorted_containers::SortedContainers;
+use std::cmp::Ordering;
+
+/// A view into a single element of a `SortedContainers<T>`, which may either be vacant or
+/// occupied. It's constructed from the `entry` method of `SortedContainers`.
+///
+/// The entry remembers the position found by the search, so the element can be inserted,
+/// modified or removed without searching it again.
+pub enum Entry<'a, T> {
+    /// the collection contains an element equal to the searched one
+    Occupied(OccupiedEntry<'a, T>),
+    /// the collection doesn't contain an element equal to the searched one
+    Vacant(VacantEntry<'a, T>),
+}
+/// A view into an element of a `SortedContainers<T>` equal to the searched one.
+pub struct OccupiedEntry<'a, T> {
+    sorted_containers: &'a mut SortedContainers<T>,
+    pos: (usize, usize),
+}
+/// A view into the position of a `SortedContainers<T>` where the searched element would be
+/// inserted.
+pub struct VacantEntry<'a, T> {
+    sorted_containers: &'a mut SortedContainers<T>,
+    pos: (usize, usize),
+}
+impl<'a, T> Entry<'a, T> {
+    pub(crate) fn new(
+        sorted_containers: &'a mut SortedContainers<T>,
+        search_result: Result<(usize, usize), (usize, usize)>,
+    ) -> Self {
+        match search_result {
+            Ok(pos) => Entry::Occupied(OccupiedEntry {
+                sorted_containers,
+                pos,
+            }),
+            Err(pos) => Entry::Vacant(VacantEntry {
+                sorted_containers,
+                pos,
+            }),
+        }
+    }
+    /// Returns the position of the element in the collection, or the position where it would
+    /// be inserted if the entry is vacant.
+    pub fn index(&self) -> usize {
+        match self {
+            Entry::Occupied(entry) => entry.index(),
+            Entry::Vacant(entry) => entry.index(),
+        }
+    }
+    /// Insert the element in input if the entry is vacant, returning a mutable reference to the
+    /// element of the entry.
+    ///
+    /// Panics if the entry is vacant and the element in input doesn't sort into its position.
+    /// See `VacantEntry::insert`.
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        self.or_insert_with(|| value)
+    }
+    /// Insert the element returned by the function in input if the entry is vacant, returning a
+    /// mutable reference to the element of the entry.
+    ///
+    /// Panics with "the element does not belong to the entry position" if the entry is vacant
+    /// and the element returned by the function doesn't sort into its position. See
+    /// `VacantEntry::insert`.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+    /// Modify in place the element of an occupied entry, the entry is returned to allow chaining
+    /// `or_insert_with`.
+    ///
+    /// The function must not change the order of the element, otherwise the collection is left
+    /// unsorted.
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+impl<'a, T> OccupiedEntry<'a, T> {
+    /// Returns the position of the element in the collection
+    pub fn index(&self) -> usize {
+        self.sorted_containers.index_from_tuple(self.pos)
+    }
+    /// Returns a reference to the element of the entry
+    pub fn get(&self) -> &T {
+        self.sorted_containers.element_at_position(self.pos)
+    }
+    /// Returns a mutable reference to the element of the entry.
+    ///
+    /// The element must not be changed in a way that changes its order, otherwise the collection
+    /// is left unsorted.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.sorted_containers.element_at_position_mut(self.pos)
+    }
+    /// Converts the entry into a mutable reference to its element, bound to the lifetime of the
+    /// collection. See `get_mut`.
+    pub fn into_mut(self) -> &'a mut T {
+        self.sorted_containers.element_at_position_mut(self.pos)
+    }
+    /// Replace the element of the entry with the one in input, returning the old one.
+    ///
+    /// Panics if the element in input is not equal to the one of the entry.
+    pub fn insert(&mut self, value: T) -> T {
+        assert!(
+            self.sorted_containers.compare(&value, self.get()) == Ordering::Equal,
+            "the element does not belong to the entry position"
+        );
+        self.sorted_containers.replace_at_position(self.pos, value)
+    }
+    /// Remove the element of the entry from the collection and return it.
+    pub fn remove_entry(self) -> T {
+        self.sorted_containers.remove_at_position(self.pos)
+    }
+}
+impl<'a, T> VacantEntry<'a, T> {
+    /// Returns the position where the element would be inserted
+    pub fn index(&self) -> usize {
+        self.sorted_containers.index_from_tuple(self.pos)
+    }
+    /// Insert the element in input at the position of the entry, returning a mutable reference
+    /// to it.
+    ///
+    /// The element doesn't need to be equal to the searched one, but it must sort into the
+    /// position remembered by the entry: it must strictly follow the element before it and
+    /// strictly precede the element after it, according to the comparator of the collection.
+    ///
+    /// Panics with "the element does not belong to the entry position" otherwise.
+    pub fn insert(self, value: T) -> &'a mut T {
+        assert!(
+            self.sorted_containers.fits_at_position(self.pos, &value),
+            "the element does not belong to the entry position"
+        );
+        let pos = self.sorted_containers.insert_at_position(self.pos, value);
+        self.sorted_containers.element_at_position_mut(pos)
+    }
+}
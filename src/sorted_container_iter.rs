@@ -1,30 +1,314 @@
-use std::iter::FusedIterator;
+use crate::comparator::Comparator;
+use crate::positional_index::PositionalIndex;
+use std::cmp::Ordering;
+use std::iter::{FusedIterator, Peekable};
 
-pub struct SortedContainerIter<'a, T: Clone + Ord> {
-    pub(crate) pos: usize,
-    pub(crate) idx: usize,
-    pub(crate) data: &'a Vec<Vec<T>>,
+pub struct SortedContainerIter<'a, T> {
+    /// position of the next element returned by `next`
+    pub(crate) front: (usize, usize),
+    /// position following the next element returned by `next_back`
+    pub(crate) back: (usize, usize),
+    /// number of elements not yet returned
+    pub(crate) len: usize,
+    pub(crate) data: &'a [Vec<T>],
+    pub(crate) index: &'a PositionalIndex,
 }
 
-impl<'a, T: Clone + Ord> Iterator for SortedContainerIter<'a, T> {
+impl<'a, T> SortedContainerIter<'a, T> {
+    /// Returns an iterator over all the elements stored inside `data`.
+    pub(crate) fn new(data: &'a [Vec<T>], index: &'a PositionalIndex, len: usize) -> Self {
+        let back = match data.last() {
+            Some(values) => (data.len() - 1, values.len()),
+            None => (0, 0),
+        };
+        SortedContainerIter::between(data, index, (0, 0), back, len)
+    }
+    /// Returns an iterator over the `len` elements stored between the `front` (included) and
+    /// `back` (excluded) positions.
+    pub(crate) fn between(
+        data: &'a [Vec<T>],
+        index: &'a PositionalIndex,
+        front: (usize, usize),
+        back: (usize, usize),
+        len: usize,
+    ) -> Self {
+        SortedContainerIter {
+            front,
+            back,
+            len,
+            data,
+            index,
+        }
+    }
+}
+
+impl<'a, T> Iterator for SortedContainerIter<'a, T> {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.idx >= self.data[self.pos].len() {
-            self.pos += 1;
-            self.idx = 0;
+        if self.len == 0 {
+            return None;
+        }
+        while self.front.1 >= self.data[self.front.0].len() {
+            self.front = (self.front.0 + 1, 0);
         }
-        if self.pos >= self.data.len() {
+        self.len -= 1;
+        self.front.1 += 1;
+        Some(&self.data[self.front.0][self.front.1 - 1])
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+    /// Jump to the n-th element using the positional index.
+    /// Complexity is O(log(M))
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.len {
+            self.len = 0;
             return None;
         }
-        self.idx += 1;
-        Some(&self.data[self.pos][self.idx - 1])
+        if n > 0 {
+            self.front = self
+                .index
+                .tuple_from_index(self.index.index_from_tuple(self.front) + n);
+            self.len -= n;
+        }
+        self.next()
+    }
+}
+impl<T> DoubleEndedIterator for SortedContainerIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        while self.back.1 == 0 {
+            self.back = (self.back.0 - 1, self.data[self.back.0 - 1].len());
+        }
+        self.len -= 1;
+        self.back.1 -= 1;
+        Some(&self.data[self.back.0][self.back.1])
+    }
+    /// Jump to the n-th element from the back using the positional index.
+    /// Complexity is O(log(M))
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.len {
+            self.len = 0;
+            return None;
+        }
+        if n > 0 {
+            self.back = self
+                .index
+                .tuple_from_index(self.index.index_from_tuple(self.back) - n);
+            self.len -= n;
+        }
+        self.next_back()
+    }
+}
+impl<T> ExactSizeIterator for SortedContainerIter<'_, T> {}
+impl<T> FusedIterator for SortedContainerIter<'_, T> {}
+
+/// An iterator over the elements removed by `SortedContainers::drain`.
+pub struct Drain<T> {
+    pub(crate) inner: std::vec::IntoIter<T>,
+}
+
+impl<T> Iterator for Drain<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<T> DoubleEndedIterator for Drain<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+impl<T> ExactSizeIterator for Drain<T> {}
+impl<T> FusedIterator for Drain<T> {}
+
+/// An owning iterator over the elements of a `SortedContainers`, moving them out sub-vector
+/// by sub-vector.
+pub struct IntoIter<T> {
+    pub(crate) data: std::vec::IntoIter<Vec<T>>,
+    pub(crate) front: std::vec::IntoIter<T>,
+    pub(crate) back: std::vec::IntoIter<T>,
+    pub(crate) len: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.front.next() {
+                self.len -= 1;
+                return Some(element);
+            }
+            match self.data.next() {
+                Some(values) => self.front = values.into_iter(),
+                None => break,
+            }
+        }
+        let element = self.back.next()?;
+        self.len -= 1;
+        Some(element)
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let mut max = 0;
-        for vec in self.data {
-            max += vec.len();
+        (self.len, Some(self.len))
+    }
+}
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.back.next_back() {
+                self.len -= 1;
+                return Some(element);
+            }
+            match self.data.next_back() {
+                Some(values) => self.back = values.into_iter(),
+                None => break,
+            }
+        }
+        let element = self.front.next_back()?;
+        self.len -= 1;
+        Some(element)
+    }
+}
+impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T> FusedIterator for IntoIter<T> {}
+
+/// An iterator over a collection, following its order or the reversed one.
+pub(crate) struct OrderedIter<'a, T> {
+    pub(crate) inner: SortedContainerIter<'a, T>,
+    pub(crate) reversed: bool,
+}
+
+impl<'a, T> Iterator for OrderedIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reversed {
+            self.inner.next_back()
+        } else {
+            self.inner.next()
         }
-        (0, Some(max))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<T> ExactSizeIterator for OrderedIter<'_, T> {}
+
+/// Merge two sorted iterators, returning at each step the lowest element of either side, or
+/// both of them if they are equal.
+pub(crate) struct MergeIter<'a, T> {
+    pub(crate) a: Peekable<OrderedIter<'a, T>>,
+    pub(crate) b: Peekable<OrderedIter<'a, T>>,
+    pub(crate) comparator: &'a dyn Comparator<T>,
+}
+
+impl<'a, T> Iterator for MergeIter<'a, T> {
+    type Item = (Option<&'a T>, Option<&'a T>);
+    fn next(&mut self) -> Option<Self::Item> {
+        let ordering = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(a), Some(b)) => self.comparator.compare(a, b),
+        };
+        match ordering {
+            Ordering::Less => Some((self.a.next(), None)),
+            Ordering::Equal => Some((self.a.next(), self.b.next())),
+            Ordering::Greater => Some((None, self.b.next())),
+        }
+    }
+}
+impl<T> MergeIter<'_, T> {
+    /// returns the number of elements left on either side
+    fn lens(&self) -> (usize, usize) {
+        (self.a.len(), self.b.len())
+    }
+}
+
+/// A lazy iterator over the elements of either collection, see `SortedContainers::union`.
+pub struct Union<'a, T> {
+    pub(crate) inner: MergeIter<'a, T>,
+}
+
+impl<'a, T> Iterator for Union<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (a, b) = self.inner.next()?;
+        a.or(b)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.inner.lens();
+        (a.max(b), Some(a + b))
+    }
+}
+impl<T> FusedIterator for Union<'_, T> {}
+
+/// A lazy iterator over the elements of both collections, see `SortedContainers::intersection`.
+pub struct Intersection<'a, T> {
+    pub(crate) inner: MergeIter<'a, T>,
+}
+
+impl<'a, T> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(a), Some(_)) = self.inner.next()? {
+                return Some(a);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.inner.lens();
+        (0, Some(a.min(b)))
+    }
+}
+impl<T> FusedIterator for Intersection<'_, T> {}
+
+/// A lazy iterator over the elements of the first collection missing from the second one,
+/// see `SortedContainers::difference`.
+pub struct Difference<'a, T> {
+    pub(crate) inner: MergeIter<'a, T>,
+}
+
+impl<'a, T> Iterator for Difference<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(a), None) = self.inner.next()? {
+                return Some(a);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.inner.lens();
+        (a.saturating_sub(b), Some(a))
+    }
+}
+impl<T> FusedIterator for Difference<'_, T> {}
+
+/// A lazy iterator over the elements of exactly one of the two collections,
+/// see `SortedContainers::symmetric_difference`.
+pub struct SymmetricDifference<'a, T> {
+    pub(crate) inner: MergeIter<'a, T>,
+}
+
+impl<'a, T> Iterator for SymmetricDifference<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                (Some(_), Some(_)) => continue,
+                (a, b) => return a.or(b),
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.inner.lens();
+        (0, Some(a + b))
     }
 }
-impl<T: Ord + Clone> FusedIterator for SortedContainerIter<'_, T> {}
+impl<T> FusedIterator for SymmetricDifference<'_, T> {}
@@ -1,9 +1,19 @@
+use crate::comparator::Comparator;
 use crate::errors::SortedContainersError;
-use crate::sorted_container_iter::SortedContainerIter;
+use crate::positional_index::PositionalIndex;
+use crate::rebalance_policy::{FixedLoadFactor, RebalanceContext, RebalancePolicy, StrategyPolicy};
+use crate::sorted_container_entry::Entry;
+use crate::sorted_container_iter::{
+    Difference, Drain, Intersection, IntoIter, MergeIter, OrderedIter, SortedContainerIter,
+    SymmetricDifference, Union,
+};
+use crate::sorted_containers_builder::SortedContainersBuilder;
 use std::cmp::Ordering;
-use std::ops::Index;
-use std::ptr;
+use std::mem;
+use std::ops::{BitAnd, BitOr, BitXor, Bound, Index, Range, RangeBounds, Sub};
+use std::sync::Arc;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OrderType {
     Asc,
     Desc,
@@ -16,27 +26,18 @@ enum ProcessType {
 }
 pub struct SortedContainers<T> {
     data: Vec<Vec<T>>,
-    maxes: Vec<T>,
-    index: Vec<usize>,
-    order_type: OrderType,
+    index: PositionalIndex,
+    comparator: Arc<dyn Comparator<T>>,
+    allow_duplicates: bool,
     len: usize,
-    expand_strategy: fn(usize, usize) -> bool,
-    shrink_strategy: fn(usize, usize) -> bool,
+    policy: Box<dyn RebalancePolicy>,
 }
-impl<T: Ord + Clone> Default for SortedContainers<T> {
+impl<T: Ord> Default for SortedContainers<T> {
     fn default() -> Self {
-        SortedContainers {
-            data: vec![Vec::new()],
-            maxes: Vec::new(),
-            index: Vec::new(),
-            order_type: OrderType::Asc,
-            len: 0,
-            expand_strategy: |len, _pos| len > 2000,
-            shrink_strategy: |len, _pos| len < 500,
-        }
+        SortedContainers::new(OrderType::Asc)
     }
 }
-impl<T: Ord + Clone> SortedContainers<T> {
+impl<T: Ord> SortedContainers<T> {
     /// Constructs a new empty `SortedContainers<T>` with the specified order type
     ///
     /// The collection will store in ascending or descending order the elements later inserted.
@@ -47,29 +48,116 @@ impl<T: Ord + Clone> SortedContainers<T> {
     /// let mut sorted_containers = SortedContainers::new(OrderType::Desc);
     /// // the sorted collection will store in descending order the input elements
     pub fn new(order_type: OrderType) -> SortedContainers<T> {
+        SortedContainers::new_with_comparator(order_type)
+    }
+    /// Constructs a new empty multiset `SortedContainers<T>` with the specified order type
+    ///
+    /// Unlike `new`, the collection accepts elements that are equal to the ones already stored.
+    /// Equal elements are kept adjacent in insertion order.
+    ///
+    /// # Examples
+    /// let mut samples = SortedContainers::new_multiset(OrderType::Asc);
+    /// samples.insert(42);
+    /// samples.insert(42);
+    /// assert_eq!(samples.count(&42), 2);
+    pub fn new_multiset(order_type: OrderType) -> SortedContainers<T> {
         SortedContainers {
-            data: vec![Vec::new()],
-            maxes: Vec::new(),
-            index: Vec::new(),
-            order_type,
-            len: 0,
-            expand_strategy: |len, _pos| len > 2000,
-            shrink_strategy: |len, _pos| len < 500,
+            allow_duplicates: true,
+            ..SortedContainers::new(order_type)
         }
     }
+    /// Constructs a new empty `SortedContainers<T>` with the specified order type, splitting a
+    /// sub-vector when `expand_strategy` returns `true` and merging it when `shrink_strategy`
+    /// returns `true`. Both strategies receive the length and the position of the sub-vector.
+    /// See `builder` to configure the collection with closures or a `RebalancePolicy`.
     pub fn new_with_strategies(
         order_type: OrderType,
         expand_strategy: fn(usize, usize) -> bool,
         shrink_strategy: fn(usize, usize) -> bool,
     ) -> SortedContainers<T> {
+        SortedContainers {
+            policy: Box::new(StrategyPolicy::new(
+                move |context: &RebalanceContext| expand_strategy(context.len, context.pos),
+                move |context: &RebalanceContext| shrink_strategy(context.len, context.pos),
+            )),
+            ..SortedContainers::new(order_type)
+        }
+    }
+    /// Returns a builder to configure the order, the duplicate policy, the load factor and the
+    /// rebalance policy of a new collection.
+    ///
+    /// # Examples
+    /// let sorted_containers: SortedContainers<i32> = SortedContainers::builder()
+    ///     .order(OrderType::Desc)
+    ///     .load_factor(500)
+    ///     .with_capacity(100_000)
+    ///     .build();
+    pub fn builder() -> SortedContainersBuilder<T> {
+        SortedContainersBuilder::new()
+    }
+    /// Constructs a new `SortedContainers<T>` with the specified order type, containing the
+    /// elements of the vector in input.
+    ///
+    /// The vector is expected to be sorted following the order type: its elements are then
+    /// splitted directly into sub-vectors and the positional index is built once, in O(N).
+    /// If the vector is not sorted it's sorted first. Duplicated elements are discarded,
+    /// keeping the first one.
+    ///
+    /// # Examples
+    /// let sorted_containers = SortedContainers::from_sorted_vec(OrderType::Asc, vec![1, 2, 3]);
+    pub fn from_sorted_vec(order_type: OrderType, values: Vec<T>) -> SortedContainers<T> {
+        let mut sorted_containers = SortedContainers::new(order_type);
+        sorted_containers.load(values);
+        sorted_containers
+    }
+    /// Constructs a new `SortedContainers<T>` with the specified order type, containing the
+    /// elements of the iterator in input. See `from_sorted_vec`.
+    pub fn from_sorted_iter<I: IntoIterator<Item = T>>(
+        order_type: OrderType,
+        iter: I,
+    ) -> SortedContainers<T> {
+        SortedContainers::from_sorted_vec(order_type, iter.into_iter().collect())
+    }
+}
+impl<T> SortedContainers<T> {
+    /// Constructs a new empty `SortedContainers<T>` sorted by the comparator in input
+    ///
+    /// Every comparison performed by the collection is delegated to the comparator, elements
+    /// for which the comparator returns `Ordering::Equal` are considered the same element.
+    ///
+    /// # Examples
+    /// let mut sorted_containers = SortedContainers::new_with_comparator(|a: &String, b: &String| {
+    ///     a.to_lowercase().cmp(&b.to_lowercase())
+    /// });
+    /// // the sorted collection will store the input strings ignoring the case
+    pub fn new_with_comparator<C: Comparator<T> + 'static>(comparator: C) -> SortedContainers<T> {
         SortedContainers {
             data: vec![Vec::new()],
-            maxes: Vec::new(),
-            index: Vec::new(),
-            order_type,
+            index: PositionalIndex::default(),
+            comparator: Arc::new(comparator),
+            allow_duplicates: false,
+            len: 0,
+            policy: Box::new(FixedLoadFactor::default()),
+        }
+    }
+    /// constructs a new empty collection from the parts configured by a builder, reserving room
+    /// for `capacity` elements
+    pub(crate) fn from_parts(
+        comparator: Arc<dyn Comparator<T>>,
+        allow_duplicates: bool,
+        policy: Box<dyn RebalancePolicy>,
+        capacity: usize,
+    ) -> SortedContainers<T> {
+        let load_factor = policy.load_factor(capacity);
+        let mut data = Vec::with_capacity(capacity.div_ceil(load_factor).max(1));
+        data.push(Vec::with_capacity(capacity.min(load_factor)));
+        SortedContainers {
+            data,
+            index: PositionalIndex::default(),
+            comparator,
+            allow_duplicates,
             len: 0,
-            expand_strategy,
-            shrink_strategy,
+            policy,
         }
     }
     /// Returns the number of elements in the sortedcontainers, also referred as its 'length'.
@@ -80,6 +168,10 @@ impl<T: Ord + Clone> SortedContainers<T> {
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
+    /// Returns `true` if the collection accepts duplicate elements
+    pub fn is_multiset(&self) -> bool {
+        self.allow_duplicates
+    }
     /// Returns the current number of sub-vectors
     pub fn depth(&self) -> usize {
         self.data.len()
@@ -87,14 +179,13 @@ impl<T: Ord + Clone> SortedContainers<T> {
     /// Remove all the elements inside the sortedcontainers.
     pub fn clear(&mut self) {
         self.data.clear();
-        self.maxes.clear();
         self.index.clear();
         self.len = 0;
     }
     /// Search an element inside the collection.
     /// Complexity is O(log(M)) + O(log(N))
     /// If the element exists in the collection the actual position is returned otherwise
-    /// an error is returned
+    /// an error is returned. In a multiset the position of the first equal element is returned.
     pub fn find(&self, element: &T) -> Option<usize> {
         match self.search_element(element) {
             Ok(pos) => Some(self.index_from_tuple(pos)),
@@ -106,10 +197,85 @@ impl<T: Ord + Clone> SortedContainers<T> {
     /// If the element is not currently inside the collection, the element is inserted
     /// and the actual position is returned.
     /// Complexity is O(log(M)) + O(log(N)) + O(N)
-    /// If the element already exists, an error is returned.
+    /// If the element already exists, an error is returned, unless the collection is a multiset:
+    /// in that case the element is inserted after the equal elements already stored.
     pub fn insert(&mut self, value: T) -> Result<usize, SortedContainersError<T>> {
         self.process_element(value, ProcessType::Insert)
     }
+    /// Insert a batch of elements inside the collection.
+    ///
+    /// The batch is sorted once, then the position of every element is searched walking the
+    /// sub-vectors a single time. Each sub-vector receiving elements is then merged with them,
+    /// overfull sub-vectors are splitted and the index is rebuilt once, so the complexity is
+    /// O(K log(K)) + O(K log(N)) + O(M) + O(N) for each sub-vector receiving elements.
+    /// Returns how many elements were inserted and how many were rejected because already
+    /// inside the collection or repeated inside the batch, as `(inserted, rejected)`.
+    /// A multiset never rejects elements.
+    /// All the comparisons are performed before moving any element, so if the comparator panics
+    /// the collection is left unchanged.
+    pub fn insert_batch<I: IntoIterator<Item = T>>(&mut self, iter: I) -> (usize, usize) {
+        let mut batch: Vec<T> = iter.into_iter().collect();
+        let batch_len = batch.len();
+        if self.is_empty() {
+            self.load(batch);
+            return (self.len, batch_len - self.len);
+        }
+        if batch.is_empty() {
+            return (0, 0);
+        }
+        batch.sort_by(|a, b| self.compare(a, b));
+        if !self.allow_duplicates {
+            batch.dedup_by(|a, b| self.compare(a, b) == Ordering::Equal);
+        }
+        let positions = self.batch_positions(&batch);
+        let inserted = positions.iter().flatten().count();
+        let mut lens: Vec<usize> = self.data.iter().map(|values| values.len()).collect();
+        positions
+            .iter()
+            .flatten()
+            .for_each(|(pos, _)| lens[*pos] += 1);
+        let mut batch = batch
+            .into_iter()
+            .zip(positions)
+            .filter_map(|(value, position)| Some((value, position?)))
+            .peekable();
+        let mut merged_into = vec![false; self.data.len()];
+        for (pos, sub_vector) in self.data.iter_mut().enumerate() {
+            if lens[pos] == sub_vector.len() {
+                continue;
+            }
+            merged_into[pos] = true;
+            let mut merged = Vec::with_capacity(lens[pos]);
+            let mut values = mem::take(sub_vector).into_iter();
+            let mut moved = 0;
+            while let Some((value, (_, idx))) = batch.next_if(|(_, position)| position.0 == pos) {
+                merged.extend(values.by_ref().take(idx - moved));
+                moved = idx;
+                merged.push(value);
+            }
+            merged.extend(values);
+            *sub_vector = merged;
+        }
+        self.len += inserted;
+        // the merged sub-vectors the policy asks to split are splitted evenly in sub-vectors of
+        // about `load_factor` elements
+        let load_factor = self.policy.load_factor(self.len);
+        let mut splits = vec![1; self.data.len()];
+        for pos in 0..self.data.len() {
+            if merged_into[pos] && self.should_expand(pos) {
+                splits[pos] = self.data[pos].len().div_ceil(load_factor).max(2);
+            }
+        }
+        if splits.iter().any(|&split| split > 1) {
+            let mut data = Vec::with_capacity(splits.iter().sum());
+            for (values, split) in mem::take(&mut self.data).into_iter().zip(splits) {
+                data.extend(Self::split_evenly(values, split));
+            }
+            self.data = data;
+        }
+        self.rebalance_or_build_index();
+        (inserted, batch_len - inserted)
+    }
     /// Update an element inside the collection.
     /// Complexity is O(log(M)) + O(log(N))
     /// If the element exists in the collection, the element will be updated returning the actual
@@ -123,94 +289,543 @@ impl<T: Ord + Clone> SortedContainers<T> {
     pub fn insert_or_update(&mut self, value: T) -> Result<usize, SortedContainersError<T>> {
         self.process_element(value, ProcessType::InsertOrUpdate)
     }
+    /// Insert an element inside the collection, replacing the equal element if it already exists.
+    /// The replaced element is returned, like `HashSet::replace`.
+    /// In a multiset the first equal element is replaced.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N)
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        self.replace_full(value).1
+    }
+    /// Insert an element inside the collection, replacing the equal element if it already exists.
+    /// Returns the position of the element together with the replaced element, if any.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N)
+    pub fn replace_full(&mut self, value: T) -> (usize, Option<T>) {
+        match self.search_element(&value) {
+            Ok(pos) => {
+                let replaced = self.replace_at_position(pos, value);
+                (self.index_from_tuple(pos), Some(replaced))
+            }
+            Err(pos) => {
+                let pos = self.insert_at_position(pos, value);
+                (self.index_from_tuple(pos), None)
+            }
+        }
+    }
+    /// Returns the entry of the element equal to the one in input, to insert, modify or remove
+    /// it in place without searching it again.
+    /// In a multiset the entry refers to the first equal element.
+    /// Complexity is O(log(M)) + O(log(N))
+    ///
+    /// # Examples
+    /// let mut sorted_containers = SortedContainers::new(OrderType::Asc);
+    /// sorted_containers.entry(&42).or_insert(42);
+    pub fn entry(&mut self, key: &T) -> Entry<'_, T> {
+        let search_result = self.search_element(key);
+        Entry::new(self, search_result)
+    }
     /// Remove an element that is stored inside the collection.
     /// Time complexity O(log(M)) + O(log(N)) + O(N)
     /// Given an element in input, a search is perfoemd. If the element exists inside the collection,
     /// the element is removed and returned. Otherwise an error is returned.
+    /// In a multiset the first equal element is removed.
     pub fn remove(&mut self, value: &T) -> Option<T> {
         match self.search_element(value) {
-            Ok((pos, idx)) => {
-                let removed_val = self.data[pos].remove(idx);
-                self.update_index(pos, -1);
-                self.len -= 1;
-                if self.is_empty() {
-                    self.maxes.clear();
-                    self.data.clear();
-                    self.index.clear();
-                    return Some(removed_val);
-                }
-                if self.maxes.len() > 1 && (self.shrink_strategy)(self.data[pos].len(), pos) {
-                    self.shrink(pos);
-                }
-                Some(removed_val)
-            }
+            Ok(pos) => Some(self.remove_at_position(pos)),
             Err(_) => None,
         }
     }
-    /// Return a vector of elements in a specified range.
-    /// Panics in the following scenarios:
-    /// 1. start > end
-    /// 2. start >= collection length
-    /// 3. end >= collection length
-    pub fn range(&self, start: usize, end: usize) -> Option<Vec<T>> {
-        if start > end {
-            panic!("start position is greater than end position");
+    /// Remove the first element equal to the one in input, i.e. the oldest inserted one.
+    /// Time complexity O(log(M)) + O(log(N)) + O(N)
+    pub fn remove_one(&mut self, value: &T) -> Option<T> {
+        self.remove(value)
+    }
+    /// Remove all the elements equal to the one in input, returning how many were removed.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let range = self.equal_range(value);
+        self.remove_range(range)
+    }
+    /// Remove the elements in the range of positions in input, returning them as an iterator.
+    ///
+    /// The elements are removed from the collection even if the iterator is not consumed.
+    /// The interior sub-vectors are dropped at once and the positional index is rebuilt a
+    /// single time, so the complexity is O(log(M)) + O(M) + O(N) plus the removed elements.
+    /// Panics if the start of the range follows its end or if the end is greater than the
+    /// collection length.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<T> {
+        let (start, end) = self.positions_from_range(range);
+        let mut removed = Vec::with_capacity(end - start);
+        self.remove_positions(start, end, |element| removed.push(element));
+        Drain {
+            inner: removed.into_iter(),
         }
-        if start >= self.len() {
-            panic!("start is greater than total len");
+    }
+    /// Remove the elements in the range of positions in input, returning how many were removed.
+    ///
+    /// It behaves like `drain` without collecting the removed elements.
+    /// Panics if the start of the range follows its end or if the end is greater than the
+    /// collection length.
+    pub fn remove_range<R: RangeBounds<usize>>(&mut self, range: R) -> usize {
+        let (start, end) = self.positions_from_range(range);
+        self.remove_positions(start, end, drop);
+        end - start
+    }
+    /// Remove the element at the position in input and return it.
+    /// Time complexity O(log(M)) + O(N)
+    /// Panics if the position is greater or equal than the collection length.
+    pub fn remove_at(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "index out of bound");
+        let pos = self.tuple_from_index(index);
+        self.remove_at_position(pos)
+    }
+    /// Remove the element at the position in input and return it, or `None` if the position
+    /// is greater or equal than the collection length.
+    /// Time complexity O(log(M)) + O(N)
+    pub fn pop(&mut self, index: usize) -> Option<T> {
+        if index < self.len() {
+            Some(self.remove_at(index))
+        } else {
+            None
         }
-        if end >= self.len() {
-            panic!("end is greater than total len");
+    }
+    /// Remove the first element of the collection and return it.
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.pop(0)
+    }
+    /// Remove the last element of the collection and return it.
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.len.checked_sub(1).and_then(|index| self.pop(index))
+    }
+    /// Returns the first element of the collection.
+    pub fn first(&self) -> Option<&T> {
+        self.iter().next()
+    }
+    /// Returns the last element of the collection.
+    pub fn last(&self) -> Option<&T> {
+        self.data.iter().rev().find_map(|values| values.last())
+    }
+    /// Returns the greatest element lower than or equal to the one in input together with its
+    /// position. The elements are compared by value in a descending collection too, so there
+    /// the element is searched from the input onward.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn floor(&self, value: &T) -> Option<(usize, &T)> {
+        if self.is_descending() {
+            self.neighbour_after(value, true)
+        } else {
+            self.neighbour_before(value, true)
         }
-        let mut vec = Vec::new();
-        for i in start..end {
-            let pos = self.tuple_from_index(i);
-            vec.push(self.data[pos.0][pos.1].clone());
+    }
+    /// Returns the lowest element greater than or equal to the one in input together with its
+    /// position. See `floor`.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn ceiling(&self, value: &T) -> Option<(usize, &T)> {
+        if self.is_descending() {
+            self.neighbour_before(value, true)
+        } else {
+            self.neighbour_after(value, true)
         }
-        if !vec.is_empty() {
-            Some(vec)
+    }
+    /// Returns the greatest element strictly lower than the one in input together with its
+    /// position. See `floor`.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn lower(&self, value: &T) -> Option<(usize, &T)> {
+        if self.is_descending() {
+            self.neighbour_after(value, false)
         } else {
-            None
+            self.neighbour_before(value, false)
+        }
+    }
+    /// Returns the lowest element strictly greater than the one in input together with its
+    /// position. See `floor`.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn higher(&self, value: &T) -> Option<(usize, &T)> {
+        if self.is_descending() {
+            self.neighbour_before(value, false)
+        } else {
+            self.neighbour_after(value, false)
+        }
+    }
+    /// Returns the element closest to the one in input according to the distance function,
+    /// together with its position. Only the `floor` and the `ceiling` of the input are
+    /// evaluated; if they are equally distant the `floor` is returned.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn closest_by<D: PartialOrd, F: Fn(&T, &T) -> D>(
+        &self,
+        value: &T,
+        distance: F,
+    ) -> Option<(usize, &T)> {
+        match (self.floor(value), self.ceiling(value)) {
+            (Some(floor), Some(ceiling)) => {
+                if distance(ceiling.1, value) < distance(floor.1, value) {
+                    Some(ceiling)
+                } else {
+                    Some(floor)
+                }
+            }
+            (floor, ceiling) => floor.or(ceiling),
         }
     }
+    /// Returns the number of elements equal to the one in input.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn count(&self, value: &T) -> usize {
+        self.equal_range(value).len()
+    }
+    /// Returns the range of positions occupied by the elements equal to the one in input.
+    /// If no element is equal, the returned range is empty and starts at the position where
+    /// the element would be inserted.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn equal_range(&self, value: &T) -> Range<usize> {
+        self.bisect_left(value)..self.bisect_right(value)
+    }
+    /// Returns the position where the element in input would be inserted, before any equal
+    /// element already stored. The element doesn't need to be inside the collection.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn bisect_left(&self, value: &T) -> usize {
+        self.index_from_tuple(self.search_bound(value, false))
+    }
+    /// Returns the position where the element in input would be inserted, after any equal
+    /// element already stored. The element doesn't need to be inside the collection.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn bisect_right(&self, value: &T) -> usize {
+        self.index_from_tuple(self.search_bound(value, true))
+    }
+    /// Returns the number of elements that precede the element in input, i.e. the elements
+    /// lower than the input for an ascending collection.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn rank(&self, value: &T) -> usize {
+        self.bisect_left(value)
+    }
+    /// Returns the number of elements between `low` (included) and `high` (excluded), following
+    /// the order of the collection. If `low` follows `high`, 0 is returned.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn count_range(&self, low: &T, high: &T) -> usize {
+        self.bisect_left(high).saturating_sub(self.bisect_left(low))
+    }
+    /// Returns an iterator over the elements contained in the range of values in input.
+    ///
+    /// The bounds follow the order of the collection: in a descending collection `9..=3` returns
+    /// the elements from 9 down to 3. If the start bound follows the end bound the iterator is
+    /// empty.
+    /// Complexity is O(log(M)) + O(log(N)) to locate the bounds, the elements are then
+    /// borrowed lazily while iterating.
+    pub fn range_by_value<R: RangeBounds<T>>(&self, range: R) -> SortedContainerIter<'_, T> {
+        let start = match range.start_bound() {
+            Bound::Included(value) => self.search_bound(value, false),
+            Bound::Excluded(value) => self.search_bound(value, true),
+            Bound::Unbounded => (0, 0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(value) => self.search_bound(value, true),
+            Bound::Excluded(value) => self.search_bound(value, false),
+            Bound::Unbounded => match self.data.last() {
+                Some(values) => (self.data.len() - 1, values.len()),
+                None => (0, 0),
+            },
+        };
+        let (start_index, end_index) = (self.index_from_tuple(start), self.index_from_tuple(end));
+        if start_index >= end_index {
+            return SortedContainerIter::between(&self.data, &self.index, start, start, 0);
+        }
+        SortedContainerIter::between(&self.data, &self.index, start, end, end_index - start_index)
+    }
     // Returns an iterator over the collection
     pub fn iter(&self) -> SortedContainerIter<'_, T> {
-        SortedContainerIter {
-            data: &self.data,
-            pos: 0,
-            idx: 0,
+        SortedContainerIter::new(&self.data, &self.index, self.len)
+    }
+    /// replace the content of the collection with the elements in input, sorting and
+    /// deduplicating them only if needed. The elements are splitted in sub-vectors of about
+    /// `load_factor` elements, then the index is built once.
+    fn load(&mut self, mut values: Vec<T>) {
+        if !values.is_sorted_by(|a, b| self.compare(a, b) != Ordering::Greater) {
+            values.sort_by(|a, b| self.compare(a, b));
         }
+        if !self.allow_duplicates {
+            values.dedup_by(|a, b| self.compare(a, b) == Ordering::Equal);
+        }
+        self.distribute(values);
     }
-    /// given an position in input, the element at `self.data[position]` is splitted in half and the
-    /// second part is inserted at `position + 1` inside the `self.data`
-    #[inline]
-    fn expand(&mut self, pos: usize) {
-        let c_len = self.data[pos].len();
-        let split_at = c_len / 2;
-        let new_len = self.data[pos].len() - split_at;
-        let mut new_vec = Vec::with_capacity(c_len);
-        unsafe {
-            self.data[pos].set_len(split_at);
-            new_vec.set_len(new_len);
-            ptr::copy_nonoverlapping(
-                self.data[pos].as_ptr().add(split_at),
-                new_vec.as_mut_ptr(),
-                new_vec.len(),
-            );
+    /// replace the content of the collection with the sorted elements in input, splitted in
+    /// sub-vectors of about `load_factor` elements, then the index is built once.
+    fn distribute(&mut self, values: Vec<T>) {
+        self.clear();
+        self.len = values.len();
+        if values.is_empty() {
+            return;
+        }
+        // the elements are distributed evenly, so that no sub-vector is left almost empty
+        let sub_vectors = values.len().div_ceil(self.policy.load_factor(values.len()));
+        self.data.extend(Self::split_evenly(values, sub_vectors));
+        self.build_index();
+    }
+    /// splits the sorted elements in input into the number of sub-vectors in input, at most the
+    /// number of elements, whose lengths differ at most by one
+    fn split_evenly(values: Vec<T>, sub_vectors: usize) -> impl Iterator<Item = Vec<T>> {
+        let sub_vectors = sub_vectors.min(values.len());
+        let (min_len, longer) = (values.len() / sub_vectors, values.len() % sub_vectors);
+        let mut values = values.into_iter();
+        (0..sub_vectors).map(move |i| {
+            let sub_vector_len = if i < longer { min_len + 1 } else { min_len };
+            values.by_ref().take(sub_vector_len).collect()
+        })
+    }
+    /// returns the position where every element of the sorted batch in input would be inserted
+    /// by `insert`, relative to the sub-vectors before the insertion, or `None` if the element
+    /// is already inside the collection.
+    fn batch_positions(&self, batch: &[T]) -> Vec<Option<(usize, usize)>> {
+        // stored elements precede the new ones, equal ones included in a multiset
+        let precedes = |stored: &T, value: &T| match self.compare(stored, value) {
+            Ordering::Less => true,
+            Ordering::Equal => self.allow_duplicates,
+            Ordering::Greater => false,
+        };
+        let last_pos = self.data.len() - 1;
+        let (mut pos, mut idx) = (0, 0);
+        batch
+            .iter()
+            .map(|value| {
+                // the batch is sorted, so the positions never move backwards
+                while pos < last_pos
+                    && self.data[pos]
+                        .last()
+                        .is_none_or(|last| precedes(last, value))
+                {
+                    (pos, idx) = (pos + 1, 0);
+                }
+                idx += self.data[pos][idx..].partition_point(|stored| precedes(stored, value));
+                let duplicated = !self.allow_duplicates
+                    && self.data[pos]
+                        .get(idx)
+                        .is_some_and(|stored| self.compare(stored, value) == Ordering::Equal);
+                (!duplicated).then_some((pos, idx))
+            })
+            .collect()
+    }
+    /// Split the collection in two at the element in input. Returns a new collection containing
+    /// the elements equal to or following the one in input, while this collection keeps the
+    /// preceding ones. See `split_off_at`.
+    /// Complexity is O(log(M)) + O(log(N)) + O(M) + O(N)
+    pub fn split_off(&mut self, value: &T) -> SortedContainers<T> {
+        let index = self.bisect_left(value);
+        self.split_off_at(index)
+    }
+    /// Split the collection in two at the position in input. Returns a new collection, sharing
+    /// the configuration of this one, containing the elements from the position onward.
+    ///
+    /// The list of sub-vectors is splitted and at most one sub-vector is splitted in two, so the
+    /// elements are moved without being compared.
+    /// Complexity is O(log(M)) + O(M) + O(N)
+    /// Panics if the position is greater than the collection length.
+    pub fn split_off_at(&mut self, index: usize) -> SortedContainers<T> {
+        assert!(index <= self.len(), "index out of bound");
+        let mut other = self.empty_like();
+        if index == self.len {
+            return other;
+        }
+        if index == 0 {
+            std::mem::swap(self, &mut other);
+            return other;
+        }
+        let (pos, idx) = self.tuple_from_index(index);
+        let mut data = self.data.split_off(pos + 1);
+        data.insert(0, self.data[pos].split_off(idx));
+        other.data = data;
+        other.len = self.len - index;
+        self.len = index;
+        for sorted_containers in [&mut *self, &mut other] {
+            sorted_containers.data.retain(|values| !values.is_empty());
+        }
+        self.rebalance_boundary(self.data.len() - 1);
+        other.rebalance_boundary(0);
+        self.rebalance_or_build_index();
+        other.rebalance_or_build_index();
+        other
+    }
+    /// Move all the elements of the other collection into this one, leaving the other empty.
+    ///
+    /// If all the elements of the other collection follow (or precede) the ones of this
+    /// collection, its sub-vectors are moved without comparing the elements in O(M), unless
+    /// the other collection is a multiset while this one is not.
+    /// Otherwise the elements are merged as a single batch, see `insert_batch`, discarding the
+    /// ones already inside this collection.
+    pub fn append(&mut self, other: &mut SortedContainers<T>) {
+        let (other_first, other_last) = match (other.first(), other.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return,
+        };
+        let sorted = self.compare(other_first, other_last) != Ordering::Greater;
+        let after_equals = if self.allow_duplicates {
+            Ordering::Equal
+        } else {
+            Ordering::Less
+        };
+        let follows = self
+            .last()
+            .is_none_or(|last| self.compare(last, other_first) <= after_equals);
+        let precedes = self
+            .first()
+            .is_some_and(|first| self.compare(other_last, first) == Ordering::Less);
+        // the elements of a multiset must be deduplicated to be moved into a set
+        let deduplicated = !other.allow_duplicates || self.allow_duplicates;
+        if !sorted || !(follows || precedes) || !deduplicated {
+            self.insert_batch(other.drain(..));
+            return;
+        }
+        let mut data = std::mem::take(&mut other.data);
+        self.data.retain(|values| !values.is_empty());
+        // number of sub-vectors preceding the junction of the two collections
+        let junction = if follows {
+            let junction = self.data.len();
+            self.data.append(&mut data);
+            junction
+        } else {
+            let junction = data.len();
+            data.append(&mut self.data);
+            self.data = data;
+            junction
+        };
+        self.len += other.len;
+        other.clear();
+        if junction > 0 {
+            self.rebalance_boundary(junction - 1);
+        }
+        self.rebalance_or_build_index();
+    }
+    /// Returns a lazy iterator over the elements contained in either collection, following the
+    /// order of this collection.
+    ///
+    /// The other collection can be sorted in the opposite direction, but it must share the same
+    /// ordering otherwise. In a multiset an element is returned as many times as the maximum
+    /// between its occurrences in the two collections.
+    /// Complexity is O(N + K)
+    pub fn union<'a>(&'a self, other: &'a SortedContainers<T>) -> Union<'a, T> {
+        Union {
+            inner: self.merge_iter(other),
+        }
+    }
+    /// Returns a lazy iterator over the elements contained in both collections, following the
+    /// order of this collection. See `union` for the requirements on the other collection.
+    /// Complexity is O(N + K)
+    pub fn intersection<'a>(&'a self, other: &'a SortedContainers<T>) -> Intersection<'a, T> {
+        Intersection {
+            inner: self.merge_iter(other),
+        }
+    }
+    /// Returns a lazy iterator over the elements of this collection that are not contained in
+    /// the other one. See `union` for the requirements on the other collection.
+    /// Complexity is O(N + K)
+    pub fn difference<'a>(&'a self, other: &'a SortedContainers<T>) -> Difference<'a, T> {
+        Difference {
+            inner: self.merge_iter(other),
+        }
+    }
+    /// Returns a lazy iterator over the elements contained in exactly one of the two collections,
+    /// following the order of this collection. See `union` for the requirements on the other
+    /// collection.
+    /// Complexity is O(N + K)
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a SortedContainers<T>,
+    ) -> SymmetricDifference<'a, T> {
+        SymmetricDifference {
+            inner: self.merge_iter(other),
         }
-        match self.order_type {
-            OrderType::Asc => {
-                self.maxes
-                    .insert(pos + 1, new_vec[new_vec.len() - 1].clone());
-                self.maxes[pos] = self.data[pos][self.data[pos].len() - 1].clone();
+    }
+    /// Returns `true` if all the elements of this collection are contained in the other one.
+    pub fn is_subset(&self, other: &SortedContainers<T>) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+    /// Returns `true` if all the elements of the other collection are contained in this one.
+    pub fn is_superset(&self, other: &SortedContainers<T>) -> bool {
+        other.len() <= self.len() && self.merge_iter(other).all(|(a, _)| a.is_some())
+    }
+    /// Returns `true` if the two collections have no element in common.
+    pub fn is_disjoint(&self, other: &SortedContainers<T>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+    /// returns an iterator merging the elements of the two collections, the elements of `other`
+    /// are visited in reverse if it's sorted in the opposite direction
+    fn merge_iter<'a>(&'a self, other: &'a SortedContainers<T>) -> MergeIter<'a, T> {
+        let reversed = match (other.first(), other.last()) {
+            (Some(first), Some(last)) => self.compare(first, last) == Ordering::Greater,
+            _ => false,
+        };
+        MergeIter {
+            a: OrderedIter {
+                inner: self.iter(),
+                reversed: false,
             }
-            OrderType::Desc => {
-                self.maxes.insert(pos + 1, new_vec[0].clone());
-                self.maxes[pos] = self.data[pos][0].clone();
+            .peekable(),
+            b: OrderedIter {
+                inner: other.iter(),
+                reversed,
             }
+            .peekable(),
+            comparator: &*self.comparator,
+        }
+    }
+    /// returns a new empty collection sharing comparator, strategies and duplicates policy
+    fn empty_like(&self) -> SortedContainers<T> {
+        SortedContainers {
+            data: vec![Vec::new()],
+            index: PositionalIndex::default(),
+            comparator: Arc::clone(&self.comparator),
+            allow_duplicates: self.allow_duplicates,
+            len: 0,
+            policy: self.policy.clone(),
+        }
+    }
+    /// describe the sub-vector at the position in input to the rebalance policy
+    #[inline]
+    fn rebalance_context(&self, pos: usize) -> RebalanceContext {
+        RebalanceContext {
+            pos,
+            len: self.data[pos].len(),
+            total_len: self.len,
+            depth: self.data.len(),
+            previous_len: pos.checked_sub(1).map(|previous| self.data[previous].len()),
+            next_len: self.data.get(pos + 1).map(|values| values.len()),
+        }
+    }
+    /// returns `true` if the rebalance policy requires to split the sub-vector in input
+    #[inline]
+    fn should_expand(&mut self, pos: usize) -> bool {
+        let context = self.rebalance_context(pos);
+        self.policy.should_expand(&context)
+    }
+    /// returns `true` if the rebalance policy requires to merge the sub-vector in input
+    #[inline]
+    fn should_shrink(&mut self, pos: usize) -> bool {
+        let context = self.rebalance_context(pos);
+        self.policy.should_shrink(&context)
+    }
+    /// returns `true` if the rebalance policy requires to rebuild all the sub-vectors
+    #[inline]
+    fn should_rebuild(&mut self) -> bool {
+        self.policy.should_rebuild(self.len, self.data.len())
+    }
+    /// rebuild all the sub-vectors with the load factor of the rebalance policy, moving the
+    /// elements without comparing them
+    /// Complexity is O(N)
+    fn rebuild(&mut self) {
+        let values = mem::take(&mut self.data).into_iter().flatten().collect();
+        self.distribute(values);
+    }
+    /// rebuild all the sub-vectors if the rebalance policy requires it, otherwise only the
+    /// index, after the sub-vectors are changed in bulk
+    fn rebalance_or_build_index(&mut self) {
+        if self.should_rebuild() {
+            self.rebuild();
+        } else {
+            self.build_index();
         }
+    }
+    /// given an position in input, the element at `self.data[position]` is splitted in half and the
+    /// second part is inserted at `position + 1` inside the `self.data`
+    #[inline]
+    fn expand(&mut self, pos: usize) {
+        let split_at = self.data[pos].len() / 2;
         // add the second half part of the vector at position + 1
-        self.data.insert(pos + 1, new_vec);
+        let values = self.data[pos].split_off(split_at);
+        self.data.insert(pos + 1, values);
         self.build_index();
     }
     ///given a position in input, the element at `self.data[position]` is merged to the previous
@@ -230,11 +845,9 @@ impl<T: Ord + Clone> SortedContainers<T> {
         if vec_to_expand > pos {
             let mut values = self.data.remove(vec_to_expand);
             self.data[pos].append(&mut values);
-            self.maxes[pos] = self.maxes.remove(vec_to_expand);
         } else {
             let mut values = self.data.remove(pos);
             self.data[vec_to_expand].append(&mut values);
-            self.maxes[vec_to_expand] = self.maxes.remove(pos);
         }
         self.build_index();
     }
@@ -242,115 +855,290 @@ impl<T: Ord + Clone> SortedContainers<T> {
     /// or the expected position.
     /// Time complexity O(log(M)) + O(log(N))
     #[inline]
-    fn search_element(&self, value: &T) -> Result<(usize, usize), (usize, usize)> {
-        let mut pos: usize = 0;
-        if self.maxes.len() > 1 {
-            pos = self.bisect(&self.maxes, value, true).unwrap();
+    pub(crate) fn search_element(&self, value: &T) -> Result<(usize, usize), (usize, usize)> {
+        if self.data.is_empty() {
+            return Err((0, 0));
         }
-        if self.data.len() == pos {
-            pos -= 1;
+        if self.allow_duplicates {
+            // equal elements can span more sub-vectors, the leftmost one must be found
+            let (pos, idx) = self.search_bound(value, false);
+            return match self.data.get(pos).and_then(|values| values.get(idx)) {
+                Some(element) if self.compare(element, value) == Ordering::Equal => Ok((pos, idx)),
+                _ => Err((pos, idx)),
+            };
+        }
+        self.search_by(|element| self.compare(element, value))
+    }
+    /// search an element inside a collection that is not a multiset, given the ordering of the
+    /// stored elements relative to the searched one, and return the actual position or the
+    /// expected position. It allows to search by key without building a whole element.
+    /// Time complexity O(log(M)) + O(log(N))
+    #[inline]
+    pub(crate) fn search_by(
+        &self,
+        compare: impl Fn(&T) -> Ordering,
+    ) -> Result<(usize, usize), (usize, usize)> {
+        if self.data.is_empty() {
+            return Err((0, 0));
         }
-        match self.bisect(&self.data[pos], value, false) {
+        // the sub-vector is found comparing the last element of every sub-vector
+        let pos = self
+            .data
+            .partition_point(|values| {
+                values
+                    .last()
+                    .is_none_or(|last| compare(last) == Ordering::Less)
+            })
+            .min(self.data.len() - 1);
+        match self.data[pos].binary_search_by(compare) {
             Ok(idx) => Ok((pos, idx)),
             Err(idx) => Err((pos, idx)),
         }
     }
-    /// Perform binary search to a given input vector and the element to search.
-    /// If the element does not exists, the expected position is returned.
+    /// search the position where an element would be inserted keeping the collection sorted.
+    /// If `after_equals` is false the position precedes all the equal elements, otherwise it
+    /// follows them.
+    /// Time complexity O(log(M)) + O(log(N))
     #[inline]
-    fn bisect(&self, values: &[T], value: &T, bisect_maxes: bool) -> Result<usize, usize> {
-        let mut low: usize = 0;
-        let mut high: usize = values.len();
-        while low < high {
-            let middle = (high + low) >> 1;
-            match values[middle].cmp(value) {
-                Ordering::Less => match self.order_type {
-                    OrderType::Asc => low = middle + 1,
-                    OrderType::Desc => high = middle,
-                },
-                Ordering::Equal => return Ok(middle),
-                Ordering::Greater => match self.order_type {
-                    OrderType::Asc => high = middle,
-                    OrderType::Desc => low = middle + 1,
-                },
-            }
+    fn search_bound(&self, value: &T, after_equals: bool) -> (usize, usize) {
+        if self.data.is_empty() {
+            return (0, 0);
         }
-        if bisect_maxes {
-            if let OrderType::Desc = self.order_type {
-                if low == self.maxes.len() {
-                    low -= 1;
-                }
-                if low > 0 {
-                    match self.maxes[low].cmp(value) {
-                        Ordering::Less => low -= 1,
-                        Ordering::Equal => {}
-                        Ordering::Greater => {}
-                    }
-                }
-            }
-            Ok(low)
+        let precedes = |element: &T| match self.compare(element, value) {
+            Ordering::Less => true,
+            Ordering::Equal => after_equals,
+            Ordering::Greater => false,
+        };
+        let pos = self
+            .data
+            .partition_point(|values| values.last().is_none_or(precedes))
+            .min(self.data.len() - 1);
+        (
+            pos,
+            self.data[pos].partition_point(|element| precedes(element)),
+        )
+    }
+    /// returns the element at the position in input, or the first one of the next sub-vector if
+    /// the position is at the end of a sub-vector
+    #[inline]
+    fn element_at(&self, (pos, idx): (usize, usize)) -> Option<(usize, &T)> {
+        let element = match self.data.get(pos)?.get(idx) {
+            Some(element) => element,
+            None => self.data.get(pos + 1)?.first()?,
+        };
+        Some((self.index_from_tuple((pos, idx)), element))
+    }
+    /// returns the element preceding the position in input
+    #[inline]
+    fn element_before(&self, (pos, idx): (usize, usize)) -> Option<(usize, &T)> {
+        let element = if idx > 0 {
+            self.data.get(pos)?.get(idx - 1)?
         } else {
-            match self.order_type {
-                OrderType::Asc => Err(low),
-                OrderType::Desc => Err(high),
-            }
-        }
+            self.data.get(pos.checked_sub(1)?)?.last()?
+        };
+        Some((self.index_from_tuple((pos, idx)) - 1, element))
     }
-    /// Perform a binary search on the index using the position in input.
+    /// compare two elements using the comparator of the collection
     #[inline]
-    fn positional_search(&self, position: &usize) -> Result<usize, usize> {
-        let mut low: usize = 0;
-        let mut high: usize = self.index.len();
-        while low < high {
-            let middle = (high + low) >> 1;
-            match self.index[middle].cmp(position) {
-                Ordering::Less => low = middle + 1,
-                Ordering::Equal => return Ok(middle + 1),
-                Ordering::Greater => high = middle,
-            }
-        }
-        Err(low)
+    pub(crate) fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.comparator.compare(a, b)
+    }
+    /// returns `true` if the collection stores the elements in the reverse of their value order
+    #[inline]
+    pub(crate) fn is_descending(&self) -> bool {
+        self.comparator.is_descending()
+    }
+    /// returns the last element preceding the one in input in the order of the collection,
+    /// or equal to it if `inclusive` is true
+    #[inline]
+    fn neighbour_before(&self, value: &T, inclusive: bool) -> Option<(usize, &T)> {
+        self.element_before(self.search_bound(value, inclusive))
+    }
+    /// returns the first element following the one in input in the order of the collection,
+    /// or equal to it if `inclusive` is true
+    #[inline]
+    fn neighbour_after(&self, value: &T, inclusive: bool) -> Option<(usize, &T)> {
+        self.element_at(self.search_bound(value, !inclusive))
     }
     /// given an index, the function returns the actual position in the form `(usize, usize)`
+    /// Complexity is O(log(M))
     #[inline]
     fn tuple_from_index(&self, index: usize) -> (usize, usize) {
-        let mut data_pos = 0;
-        if index < self.data[0].len() {
-            return (data_pos, index);
+        self.index.tuple_from_index(index)
+    }
+    /// given a position in the form `(usize, usize)`, returns an index
+    /// Complexity is O(log(M))
+    #[inline]
+    pub(crate) fn index_from_tuple(&self, pos: (usize, usize)) -> usize {
+        self.index.index_from_tuple(pos)
+    }
+    /// compute from scratch the positional index to transform `usize` position into
+    /// `(usize, usize)`, whenever sub-vectors are splitted or merged
+    /// Complexity is O(M)
+    #[inline]
+    fn build_index(&mut self) {
+        self.index.build(&self.data);
+    }
+    /// insert the element at the position in input, expanding the sub-vector if needed, and
+    /// return the position of the element after the eventual expansion
+    #[inline]
+    pub(crate) fn insert_at_position(
+        &mut self,
+        (pos, idx): (usize, usize),
+        value: T,
+    ) -> (usize, usize) {
+        if self.is_empty() {
+            // no data inside the collection. If data is empty is needed to append an empty Vec,
+            // after that the element is pushed into data
+            if self.data.is_empty() {
+                self.data.push(Vec::new());
+            }
+            self.data[0].push(value);
+            self.len += 1;
+            self.build_index();
+            return (0, 0);
         }
-        match self.positional_search(&index) {
-            Ok(idx) => data_pos = idx - 1,
-            Err(idx) => data_pos = idx - 1,
+        // the new element is inserted, the len is increased and the index updated
+        self.data[pos].insert(idx, value);
+        self.len += 1;
+        self.index.increase(pos, 1);
+        // if the rebalance policy return true, the expand method will be called, the old vector
+        // is splitted in two and the new vector is pushed into data
+        let mut position = (pos, idx);
+        if self.should_expand(pos) {
+            let split_at = self.data[pos].len() / 2;
+            self.expand(pos);
+            if idx >= split_at {
+                position = (pos + 1, idx - split_at);
+            }
+        }
+        if self.should_rebuild() {
+            // the element keeps its index, but it's moved to another sub-vector
+            let index = self.index_from_tuple(position);
+            self.rebuild();
+            position = self.tuple_from_index(index);
         }
-        (data_pos, index - self.index[data_pos])
+        position
     }
-    /// given a position in the form `(usize, usize)`, returns an index
+    /// replace the element at the position in input with the one in input, returning the old one
+    #[inline]
+    pub(crate) fn replace_at_position(&mut self, (pos, idx): (usize, usize), value: T) -> T {
+        mem::replace(&mut self.data[pos][idx], value)
+    }
+    /// returns `true` if the element in input can be inserted at the position in input, i.e. it
+    /// strictly follows the previous element and strictly precedes the next one
+    #[inline]
+    pub(crate) fn fits_at_position(&self, pos: (usize, usize), value: &T) -> bool {
+        self.element_before(pos)
+            .is_none_or(|(_, before)| self.compare(before, value) == Ordering::Less)
+            && self
+                .element_at(pos)
+                .is_none_or(|(_, after)| self.compare(value, after) == Ordering::Less)
+    }
+    /// returns the element at the position in input
+    #[inline]
+    pub(crate) fn element_at_position(&self, (pos, idx): (usize, usize)) -> &T {
+        &self.data[pos][idx]
+    }
+    /// returns the element at the position in input, mutably
+    #[inline]
+    pub(crate) fn element_at_position_mut(&mut self, (pos, idx): (usize, usize)) -> &mut T {
+        &mut self.data[pos][idx]
+    }
+    /// remove the element at the position in input, shrinking the sub-vector if needed
     #[inline]
-    fn index_from_tuple(&self, pos: (usize, usize)) -> usize {
-        if self.data.len() > 1 {
-            return self.index[pos.0] + pos.1;
+    pub(crate) fn remove_at_position(&mut self, (pos, idx): (usize, usize)) -> T {
+        let removed_val = self.data[pos].remove(idx);
+        self.index.decrease(pos, 1);
+        self.len -= 1;
+        if self.is_empty() {
+            self.data.clear();
+            self.index.clear();
+            return removed_val;
+        }
+        if self.data[pos].is_empty() {
+            // the searches read the last element of every sub-vector, so an empty one is
+            // dropped whatever the rebalance policy says
+            self.data.remove(pos);
+            self.build_index();
+        } else if self.data.len() > 1 && self.should_shrink(pos) {
+            self.shrink(pos);
         }
-        pos.1
+        if self.should_rebuild() {
+            self.rebuild();
+        }
+        removed_val
     }
-    /// compute a positional index to transform `usize` position into `(usize, usize)`
+    /// convert a range of positions into the `(start, end)` positions, end excluded
     #[inline]
-    fn build_index(&mut self) {
-        self.index.clear();
-        if self.is_empty() || self.maxes.len() < 2 {
+    fn positions_from_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len,
+        };
+        assert!(start <= end, "start position is greater than end position");
+        assert!(end <= self.len, "end is greater than total len");
+        (start, end)
+    }
+    /// remove the elements between the `start` (included) and `end` (excluded) positions, passing
+    /// them in order to `sink`. The interior sub-vectors are removed at once, the ones at the
+    /// boundaries are truncated and eventually merged, then the index is rebuilt once.
+    fn remove_positions(&mut self, start: usize, end: usize, mut sink: impl FnMut(T)) {
+        if start == end {
             return;
         }
-        self.index.push(0);
-        for i in 0..self.data.len() {
-            self.index
-                .push(self.index[self.index.len() - 1] + self.data[i].len());
+        let (start_pos, start_idx) = self.tuple_from_index(start);
+        let (end_pos, end_idx) = if end == self.len {
+            (self.data.len() - 1, self.data[self.data.len() - 1].len())
+        } else {
+            self.tuple_from_index(end)
+        };
+        if start_pos == end_pos {
+            self.data[start_pos]
+                .drain(start_idx..end_idx)
+                .for_each(&mut sink);
+        } else {
+            self.data[start_pos].drain(start_idx..).for_each(&mut sink);
+            for values in self.data.drain(start_pos + 1..end_pos) {
+                values.into_iter().for_each(&mut sink);
+            }
+            self.data[start_pos + 1]
+                .drain(..end_idx)
+                .for_each(&mut sink);
+        }
+        self.len -= end - start;
+        if self.is_empty() {
+            self.clear();
+            return;
         }
+        self.data.retain(|values| !values.is_empty());
+        self.rebalance_boundary(start_pos);
+        self.rebalance_or_build_index();
     }
-    /// update the positional index whenever a new element is inserted or removed
-    #[inline]
-    fn update_index(&mut self, pos: usize, values_len: i32) {
-        if self.maxes.len() > 1 {
-            for i in (pos + 1)..self.index.len() {
-                self.index[i] = (self.index[i] as i32 + values_len) as usize;
+    /// the sub-vector at the position in input and the following one are merged if one of them
+    /// is too small, then splitted again if the merged one is too big. It doesn't update the
+    /// index.
+    fn rebalance_boundary(&mut self, pos: usize) {
+        let pos = pos.min(self.data.len() - 1);
+        let next = if pos + 1 < self.data.len() {
+            pos + 1
+        } else {
+            pos.saturating_sub(1)
+        };
+        if next != pos && (self.should_shrink(pos) || self.should_shrink(next)) {
+            let (low, high) = (pos.min(next), pos.max(next));
+            let mut values = self.data.remove(high);
+            self.data[low].append(&mut values);
+            if self.should_expand(low) {
+                let split_at = self.data[low].len() / 2;
+                let values = self.data[low].split_off(split_at);
+                self.data.insert(low + 1, values);
             }
         }
     }
@@ -361,30 +1149,30 @@ impl<T: Ord + Clone> SortedContainers<T> {
         value: T,
         process_type: ProcessType,
     ) -> Result<usize, SortedContainersError<T>> {
-        if self.maxes.is_empty()
+        if self.is_empty()
             && (process_type == ProcessType::Insert || process_type == ProcessType::InsertOrUpdate)
         {
-            // no data inside the collection and process_type is insert. If data is empty is needed
-            // to append an empty Vec, after that the element is pushed into data and into maxes vec
-            if self.data.is_empty() {
-                self.data.push(Vec::new());
-            }
-            self.data[0].push(value.clone());
-            self.maxes.push(value);
-            self.len += 1;
+            // no data inside the collection and process_type is insert.
+            self.insert_at_position((0, 0), value);
             Ok(0)
-        } else if self.maxes.is_empty() && process_type == ProcessType::Update {
+        } else if self.is_empty() && process_type == ProcessType::Update {
             // the collection is empty and process_type is update. An error is returned.
             Err(SortedContainersError::ElementNotFound(value))
         } else {
-            match self.search_element(&value) {
+            let search_result = if self.allow_duplicates && process_type == ProcessType::Insert {
+                // a new element is always inserted after the equal ones
+                Err(self.search_bound(&value, true))
+            } else {
+                self.search_element(&value)
+            };
+            match search_result {
                 Ok(pos) => {
                     if process_type == ProcessType::Update
                         || process_type == ProcessType::InsertOrUpdate
                     {
                         // element exist and process_type is update, the element in input will be
                         // update at the position found.
-                        self.data[pos.0][pos.1] = value;
+                        self.replace_at_position(pos, value);
                         Ok(self.index_from_tuple(pos))
                     } else {
                         // element exists and process_type is insert, an error is raised.
@@ -396,27 +1184,9 @@ impl<T: Ord + Clone> SortedContainers<T> {
                         || process_type == ProcessType::InsertOrUpdate
                     {
                         //element does not exists and process_type is insert. The element must be
-                        // inserted.
-
-                        // if the position is equal to the last element in the vector, the max
-                        // element must be updated
-                        if value > self.maxes[pos.0] {
-                            self.maxes[pos.0] = value.clone();
-                        }
-                        // the new element is inserted and the len is increased.
-                        self.data[pos.0].insert(pos.1, value);
-                        self.len += 1;
-                        // update the index
-                        self.update_index(pos.0, 1);
-                        // the inserted position is computed before the eventual expansion
-                        let final_pos = self.index_from_tuple((pos.0, pos.1));
-                        // if the expand strategy return true, the expand method will be called,
-                        // the old vector is splitted in two and the new vector is pushed into data
-                        if (self.expand_strategy)(self.data[pos.0].len(), pos.1) {
-                            self.expand(pos.0);
-                        }
-                        // the inserted position is returned
-                        Ok(final_pos)
+                        // inserted and its position returned.
+                        let pos = self.insert_at_position(pos, value);
+                        Ok(self.index_from_tuple(pos))
                     } else {
                         //element not found and process_type is update. An error is returned
                         Err(SortedContainersError::ElementNotFound(value))
@@ -426,7 +1196,67 @@ impl<T: Ord + Clone> SortedContainers<T> {
         }
     }
 }
-impl<T: Ord + Clone> Index<usize> for SortedContainers<T> {
+impl<T: Clone> SortedContainers<T> {
+    /// Return a vector of elements in a specified range.
+    /// Panics in the following scenarios:
+    /// 1. start > end
+    /// 2. start >= collection length
+    /// 3. end >= collection length
+    pub fn range(&self, start: usize, end: usize) -> Option<Vec<T>> {
+        if start > end {
+            panic!("start position is greater than end position");
+        }
+        if start >= self.len() {
+            panic!("start is greater than total len");
+        }
+        if end >= self.len() {
+            panic!("end is greater than total len");
+        }
+        let mut vec = Vec::new();
+        for i in start..end {
+            let pos = self.tuple_from_index(i);
+            vec.push(self.data[pos.0][pos.1].clone());
+        }
+        if !vec.is_empty() {
+            Some(vec)
+        } else {
+            None
+        }
+    }
+}
+impl<T: Ord> FromIterator<T> for SortedContainers<T> {
+    /// Constructs an ascending `SortedContainers<T>`, sorting the elements once and loading
+    /// them directly into sub-vectors.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        SortedContainers::from_sorted_iter(OrderType::Asc, iter)
+    }
+}
+impl<T> Extend<T> for SortedContainers<T> {
+    /// Insert the elements of the iterator as a single batch, discarding the ones already inside
+    /// the collection. See `insert_batch`.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.insert_batch(iter);
+    }
+}
+macro_rules! impl_set_operator {
+    ($trait:ident, $method:ident, $set_method:ident) => {
+        impl<T: Clone> $trait<&SortedContainers<T>> for &SortedContainers<T> {
+            type Output = SortedContainers<T>;
+
+            /// Returns a new collection, sharing the configuration of the left operand.
+            fn $method(self, rhs: &SortedContainers<T>) -> SortedContainers<T> {
+                let mut sorted_containers = self.empty_like();
+                sorted_containers.load(self.$set_method(rhs).cloned().collect());
+                sorted_containers
+            }
+        }
+    };
+}
+impl_set_operator!(BitOr, bitor, union);
+impl_set_operator!(BitAnd, bitand, intersection);
+impl_set_operator!(Sub, sub, difference);
+impl_set_operator!(BitXor, bitxor, symmetric_difference);
+impl<T> Index<usize> for SortedContainers<T> {
     type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -435,7 +1265,22 @@ impl<T: Ord + Clone> Index<usize> for SortedContainers<T> {
         &self.data[pos.0][pos.1]
     }
 }
-impl<'a, T: Ord + Clone> IntoIterator for &'a SortedContainers<T> {
+impl<T> IntoIterator for SortedContainers<T> {
+    type Item = T;
+
+    type IntoIter = IntoIter<T>;
+
+    /// Consume the collection, moving its elements out in order without cloning them.
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            data: self.data.into_iter(),
+            front: Vec::new().into_iter(),
+            back: Vec::new().into_iter(),
+            len: self.len,
+        }
+    }
+}
+impl<'a, T> IntoIterator for &'a SortedContainers<T> {
     type Item = &'a T;
 
     type IntoIter = SortedContainerIter<'a, T>;
@@ -445,11 +1290,28 @@ impl<'a, T: Ord + Clone> IntoIterator for &'a SortedContainers<T> {
     }
 }
 #[cfg(test)]
+// the baseline tests predate the lints enforced on the crate
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::bool_assert_comparison,
+    clippy::explicit_counter_loop,
+    clippy::len_zero,
+    clippy::manual_is_multiple_of
+)]
 mod test {
+    use crate::rebalance_policy::{
+        AdaptiveLoadFactor, RebalanceContext, RebalancePolicy, StrategyPolicy,
+    };
+    use crate::sorted_container_entry::Entry;
     use crate::sorted_containers::{OrderType, SortedContainers};
-    use more_asserts::{assert_gt, assert_lt};
+    use more_asserts::{assert_ge, assert_gt, assert_le, assert_lt};
     use rand::prelude::SliceRandom;
     use rand::{thread_rng, Rng};
+    use std::cmp::Ordering;
+    use std::ops::Bound;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::Arc;
 
     #[test]
     fn asc_ordered_insertion() {
@@ -459,11 +1321,10 @@ mod test {
             assert_lt!(prev_element, vec[i]);
             prev_element = vec[i];
         }
-        check_maxes(&vec, OrderType::Asc);
+        check_sub_vectors(&vec);
         vec.clear();
         assert_eq!(vec.len(), 0);
         assert_eq!(vec.data.len(), 0);
-        assert_eq!(vec.maxes.len(), 0);
         assert_eq!(vec.index.len(), 0);
     }
     #[test]
@@ -474,11 +1335,10 @@ mod test {
             assert_lt!(prev_element, vec[i]);
             prev_element = vec[i];
         }
-        check_maxes(&vec, OrderType::Asc);
+        check_sub_vectors(&vec);
         vec.clear();
         assert_eq!(vec.len(), 0);
         assert_eq!(vec.data.len(), 0);
-        assert_eq!(vec.maxes.len(), 0);
         assert_eq!(vec.index.len(), 0);
     }
     #[test]
@@ -489,12 +1349,11 @@ mod test {
             assert_gt!(prev_element, vec[i]);
             prev_element = vec[i];
         }
-        check_maxes(&vec, OrderType::Desc);
+        check_sub_vectors(&vec);
         vec.clear();
         assert_eq!(vec.len(), 0);
         assert_eq!(vec.is_empty(), true);
         assert_eq!(vec.data.len(), 0);
-        assert_eq!(vec.maxes.len(), 0);
         assert_eq!(vec.index.len(), 0);
     }
     #[test]
@@ -505,12 +1364,11 @@ mod test {
             assert_gt!(prev_element, vec[i]);
             prev_element = vec[i];
         }
-        check_maxes(&vec, OrderType::Desc);
+        check_sub_vectors(&vec);
         vec.clear();
         assert_eq!(vec.len(), 0);
         assert_eq!(vec.is_empty(), true);
         assert_eq!(vec.data.len(), 0);
-        assert_eq!(vec.maxes.len(), 0);
         assert_eq!(vec.index.len(), 0);
     }
 
@@ -555,6 +1413,29 @@ mod test {
         }
     }
     #[test]
+    fn test_replace() {
+        let mut vec =
+            SortedContainers::new_with_comparator(|a: &(i32, &str), b: &(i32, &str)| a.0.cmp(&b.0));
+        for i in 0..5_000 {
+            assert_eq!(vec.replace((i, "old")), None);
+        }
+        assert_eq!(vec.replace((4_999, "new")), Some((4_999, "old")));
+        assert_eq!(
+            vec.replace_full((2_500, "new")),
+            (2_500, Some((2_500, "old")))
+        );
+        assert_eq!(vec.replace_full((-1, "new")), (0, None));
+        assert_eq!(vec.len(), 5_001);
+        assert_eq!(vec.last(), Some(&(4_999, "new")));
+        assert_eq!(vec[2_501], (2_500, "new"));
+        check_sub_vectors_by(&vec, |a, b| a.0.cmp(&b.0));
+        match vec.entry(&(7, "")) {
+            Entry::Occupied(mut entry) => assert_eq!(entry.insert((7, "new")), (7, "old")),
+            Entry::Vacant(_) => panic!("the element must be found"),
+        }
+        assert_eq!(vec[8], (7, "new"));
+    }
+    #[test]
     fn test_remove() {
         let mut rng = thread_rng();
         let mut vec = gen_sorted_container(10_000, OrderType::Asc, true);
@@ -599,6 +1480,857 @@ mod test {
             c_element += 1;
         }
     }
+    #[test]
+    fn test_multiset_insertion() {
+        let mut rng = thread_rng();
+        for order_type in [OrderType::Asc, OrderType::Desc] {
+            let mut vec = SortedContainers::new_multiset(order_type);
+            for seq in 0..20_000 {
+                let sample = Sample {
+                    value: rng.gen_range(0..100),
+                    seq,
+                };
+                assert!(vec.insert(sample).is_ok());
+            }
+            assert_eq!(vec.len(), 20_000);
+            for i in 1..vec.len() {
+                match order_type {
+                    OrderType::Asc => assert_le!(vec[i - 1].value, vec[i].value),
+                    OrderType::Desc => assert_ge!(vec[i - 1].value, vec[i].value),
+                }
+                if vec[i - 1].value == vec[i].value {
+                    // equal elements are kept in insertion order
+                    assert_lt!(vec[i - 1].seq, vec[i].seq);
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_multiset_count_and_equal_range() {
+        for order_type in [OrderType::Asc, OrderType::Desc] {
+            let mut vec = SortedContainers::new_multiset(order_type);
+            let mut elements: Vec<i32> = (0..5_000).flat_map(|el| [el; 3]).collect();
+            elements.shuffle(&mut thread_rng());
+            for el in elements {
+                assert!(vec.insert(el).is_ok());
+            }
+            for el in 0..5_000 {
+                let range = vec.equal_range(&el);
+                assert_eq!(vec.count(&el), 3);
+                assert_eq!(range.len(), 3);
+                assert_eq!(vec.find(&el), Some(range.start));
+                for i in range {
+                    assert_eq!(vec[i], el);
+                }
+            }
+            assert_eq!(vec.count(&5_000), 0);
+            assert!(vec.equal_range(&-1).is_empty());
+        }
+        let vec = gen_sorted_container(1_000, OrderType::Asc, true);
+        assert_eq!(vec.count(&42), 1);
+        assert_eq!(vec.equal_range(&42), 1_042..1_043);
+        assert_eq!(vec.equal_range(&5_000), 2_000..2_000);
+    }
+    #[test]
+    fn test_multiset_remove() {
+        let mut vec = SortedContainers::new_multiset(OrderType::Asc);
+        for seq in 0..6_000 {
+            let sample = Sample {
+                value: (seq % 3) as i32,
+                seq,
+            };
+            assert!(vec.insert(sample).is_ok());
+        }
+        let probe = Sample { value: 1, seq: 0 };
+        // the oldest equal element is removed first
+        assert_eq!(vec.remove_one(&probe).unwrap().seq, 1);
+        assert_eq!(vec.remove_one(&probe).unwrap().seq, 4);
+        assert_eq!(vec.count(&probe), 1_998);
+        assert_eq!(vec.remove_all(&probe), 1_998);
+        assert_eq!(vec.count(&probe), 0);
+        assert_eq!(vec.len(), 4_000);
+        assert!(vec.remove_one(&probe).is_none());
+        for i in 0..vec.len() {
+            assert_eq!(vec[i].value, if i < 2_000 { 0 } else { 2 });
+        }
+    }
+    #[test]
+    fn test_comparator() {
+        let mut vec = SortedContainers::new_with_comparator(|a: &String, b: &String| {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        });
+        for word in ["banana", "Cherry", "apple", "Banana"] {
+            let _ = vec.insert(word.to_string());
+        }
+        // "Banana" is equal to "banana" for the comparator
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.find(&"BANANA".to_string()), Some(1));
+        let words: Vec<&String> = vec.iter().collect();
+        assert_eq!(words, ["apple", "banana", "Cherry"]);
+    }
+    #[test]
+    fn test_comparator_mixed_directions() {
+        // ascending on the first field, descending on the second one
+        let comparator = |a: &(i32, i32), b: &(i32, i32)| a.0.cmp(&b.0).then(b.1.cmp(&a.1));
+        let mut vec = SortedContainers::new_with_comparator(comparator);
+        let mut elements: Vec<(i32, i32)> = (0..10_000).map(|el| (el / 100, el % 100)).collect();
+        elements.shuffle(&mut thread_rng());
+        for el in elements {
+            assert!(vec.insert(el).is_ok());
+        }
+        for i in 1..vec.len() {
+            assert_eq!(comparator(&vec[i - 1], &vec[i]), Ordering::Less);
+        }
+        check_sub_vectors_by(&vec, comparator);
+        for el in [(0, 99), (42, 0), (99, 50)] {
+            assert_eq!(vec.remove(&el), Some(el));
+            assert!(vec.find(&el).is_none());
+        }
+    }
+    #[test]
+    fn test_find_after_clear() {
+        let mut vec = gen_sorted_container(1_000, OrderType::Desc, true);
+        vec.clear();
+        assert!(vec.find(&42).is_none());
+        assert!(vec.remove(&42).is_none());
+        assert_eq!(vec.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_range_by_value() {
+        let vec = gen_sorted_container(5_000, OrderType::Asc, true);
+        let values: Vec<i32> = vec.range_by_value(-42..42).copied().collect();
+        assert_eq!(values, (-42..42).collect::<Vec<i32>>());
+        let values: Vec<i32> = vec.range_by_value(-3_000..=3_000).rev().copied().collect();
+        assert_eq!(values, (-3_000..=3_000).rev().collect::<Vec<i32>>());
+        assert_eq!(vec.range_by_value(..).count(), vec.len());
+        assert_eq!(vec.range_by_value(4_000..).count(), 1_000);
+        assert_eq!(vec.range_by_value(..=-4_000).count(), 1_001);
+        let excluded = (Bound::Excluded(10), Bound::Excluded(20));
+        assert_eq!(vec.range_by_value(excluded).count(), 9);
+        assert_eq!(vec.range_by_value(42..42).count(), 0);
+        assert_eq!(
+            vec.range_by_value((Bound::Included(42), Bound::Excluded(7)))
+                .count(),
+            0
+        );
+        assert_eq!(vec.range_by_value(10_000..20_000).count(), 0);
+        let mut iter = vec.range_by_value(0..10);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.count(), 8);
+    }
+    #[test]
+    fn test_range_by_value_desc() {
+        let vec = gen_sorted_container(5_000, OrderType::Desc, true);
+        let values: Vec<i32> = vec
+            .range_by_value((Bound::Included(2_500), Bound::Included(-2_500)))
+            .copied()
+            .collect();
+        assert_eq!(values, (-2_500..=2_500).rev().collect::<Vec<i32>>());
+        assert_eq!(vec.range_by_value(-2_500..=2_500).count(), 0);
+        let mut vec = SortedContainers::new_multiset(OrderType::Desc);
+        for el in [3, 1, 2, 2, 4, 2] {
+            assert!(vec.insert(el).is_ok());
+        }
+        let values: Vec<i32> = vec
+            .range_by_value((Bound::Included(3), Bound::Excluded(1)))
+            .copied()
+            .collect();
+        assert_eq!(values, [3, 2, 2, 2]);
+        assert_eq!(
+            SortedContainers::<i32>::default()
+                .range_by_value(..)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_bisect() {
+        let mut vec = SortedContainers::default();
+        for el in (0..20_000).step_by(2) {
+            assert!(vec.insert(el).is_ok());
+        }
+        for el in 0..20_000 {
+            let position = (el as usize).div_ceil(2);
+            assert_eq!(vec.bisect_left(&el), position);
+            assert_eq!(vec.rank(&el), position);
+            if el % 2 == 0 {
+                assert_eq!(vec.bisect_right(&el), position + 1);
+            } else {
+                assert_eq!(vec.bisect_right(&el), position);
+            }
+        }
+        assert_eq!(vec.bisect_left(&-1), 0);
+        assert_eq!(vec.bisect_right(&20_000), vec.len());
+        assert_eq!(vec.count_range(&100, &200), 50);
+        assert_eq!(vec.count_range(&101, &201), 50);
+        assert_eq!(vec.count_range(&200, &100), 0);
+        assert_eq!(vec.count_range(&-100, &100_000), vec.len());
+    }
+    #[test]
+    fn test_bisect_desc_multiset() {
+        let mut vec = SortedContainers::new_multiset(OrderType::Desc);
+        for el in [5, 3, 3, 3, 1, 1] {
+            assert!(vec.insert(el).is_ok());
+        }
+        assert_eq!(vec.bisect_left(&3), 1);
+        assert_eq!(vec.bisect_right(&3), 4);
+        assert_eq!(vec.rank(&2), 4);
+        assert_eq!(vec.rank(&6), 0);
+        assert_eq!(vec.rank(&0), 6);
+        assert_eq!(vec.count_range(&4, &1), 3);
+        assert_eq!(SortedContainers::<i32>::default().rank(&42), 0);
+    }
+
+    #[test]
+    fn test_remove_at() {
+        let mut rng = thread_rng();
+        let mut vec = gen_sorted_container(10_000, OrderType::Asc, true);
+        let mut expected: Vec<i32> = (-10_000..10_000).collect();
+        while !vec.is_empty() {
+            let idx = rng.gen_range(0..vec.len());
+            assert_eq!(vec.remove_at(idx), expected.remove(idx));
+            if vec.len().is_multiple_of(1_000) && !vec.is_empty() {
+                check_sub_vectors(&vec);
+                test_index_check_trait(&vec);
+                assert_eq!(vec.iter().copied().collect::<Vec<i32>>(), expected);
+            }
+        }
+        assert!(vec.pop(0).is_none());
+    }
+    #[test]
+    #[should_panic(expected = "index out of bound")]
+    fn test_remove_at_out_of_bound() {
+        let mut vec = gen_sorted_container(10, OrderType::Asc, false);
+        vec.remove_at(20);
+    }
+    #[test]
+    fn test_pop_first_and_last() {
+        for order_type in [OrderType::Asc, OrderType::Desc] {
+            let mut vec = gen_sorted_container(5_000, order_type, true);
+            let (mut low, mut high) = match order_type {
+                OrderType::Asc => (-5_000, 4_999),
+                OrderType::Desc => (4_999, -5_000),
+            };
+            let step = if order_type == OrderType::Asc { 1 } else { -1 };
+            while !vec.is_empty() {
+                assert_eq!(vec.first(), Some(&low));
+                assert_eq!(vec.last(), Some(&high));
+                assert_eq!(vec.pop_first(), Some(low));
+                assert_eq!(vec.pop_last(), Some(high));
+                low += step;
+                high -= step;
+                if vec.len().is_multiple_of(500) && !vec.is_empty() {
+                    check_sub_vectors(&vec);
+                }
+            }
+            assert_eq!(vec.pop_first(), None);
+            assert_eq!(vec.pop_last(), None);
+            assert_eq!(vec.first(), None);
+            assert_eq!(vec.last(), None);
+        }
+        let mut vec = gen_sorted_container(100, OrderType::Asc, false);
+        assert_eq!(vec.pop(42), Some(-58));
+        assert_eq!(vec.pop(199), None);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut rng = thread_rng();
+        let mut vec = gen_sorted_container(20_000, OrderType::Asc, true);
+        let mut expected: Vec<i32> = (-20_000..20_000).collect();
+        while vec.len() > 100 {
+            let start = rng.gen_range(0..vec.len());
+            let end = rng.gen_range(start..=vec.len().min(start + 5_000));
+            let drained: Vec<i32> = vec.drain(start..end).collect();
+            assert_eq!(drained, expected.drain(start..end).collect::<Vec<i32>>());
+            assert_eq!(vec.len(), expected.len());
+            check_sub_vectors(&vec);
+            test_index_check_trait(&vec);
+            assert_eq!(vec.iter().copied().collect::<Vec<i32>>(), expected);
+        }
+        let mut drain = vec.drain(..);
+        assert_eq!(drain.len(), expected.len());
+        assert_eq!(drain.next_back(), expected.last().copied());
+        drop(drain);
+        assert!(vec.is_empty());
+        assert_eq!(vec.depth(), 0);
+        assert!(vec.insert(42).is_ok());
+        assert_eq!(vec.drain(..=0).collect::<Vec<i32>>(), [42]);
+    }
+    #[test]
+    fn test_remove_range() {
+        let mut vec = gen_sorted_container(50_000, OrderType::Desc, false);
+        let depth = vec.depth();
+        // trim the tail keeping the top 10_000 elements
+        assert_eq!(vec.remove_range(10_000..), 90_000);
+        assert_eq!(vec.len(), 10_000);
+        assert_lt!(vec.depth(), depth);
+        assert_eq!(vec.first(), Some(&49_999));
+        assert_eq!(vec.last(), Some(&40_000));
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+        assert_eq!(vec.remove_range(42..42), 0);
+        assert_eq!(vec.len(), 10_000);
+        assert!(vec.insert(-1).is_ok());
+        assert_eq!(vec.find(&-1), Some(10_000));
+    }
+    #[test]
+    fn test_remove_all_with_range() {
+        let mut vec = SortedContainers::new_multiset(OrderType::Asc);
+        for el in 0..30_000 {
+            assert!(vec.insert(el % 3).is_ok());
+        }
+        assert_eq!(vec.remove_all(&1), 10_000);
+        assert_eq!(vec.count(&0), 10_000);
+        assert_eq!(vec.count(&2), 10_000);
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+    }
+    #[test]
+    #[should_panic(expected = "end is greater than total len")]
+    fn test_drain_out_of_bound() {
+        let mut vec = gen_sorted_container(10, OrderType::Asc, false);
+        vec.drain(10..21);
+    }
+
+    #[derive(Clone, Debug)]
+    struct Sample {
+        value: i32,
+        seq: usize,
+    }
+    impl PartialEq for Sample {
+        fn eq(&self, other: &Self) -> bool {
+            self.value == other.value
+        }
+    }
+    impl Eq for Sample {}
+    impl PartialOrd for Sample {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Sample {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.value.cmp(&other.value)
+        }
+    }
+    #[test]
+    fn test_iter_double_ended() {
+        let vec = gen_sorted_container(5_000, OrderType::Asc, true);
+        let top: Vec<i32> = vec.iter().rev().take(3).copied().collect();
+        assert_eq!(top, [4_999, 4_998, 4_997]);
+        for (el, c_element) in vec.iter().rev().zip((-5_000..5_000).rev()) {
+            assert_eq!(c_element, *el);
+        }
+        let mut iter = vec.iter();
+        assert_eq!(iter.len(), 10_000);
+        assert_eq!(iter.next(), Some(&-5_000));
+        assert_eq!(iter.next_back(), Some(&4_999));
+        assert_eq!(iter.len(), 9_998);
+        assert_eq!(iter.size_hint(), (9_998, Some(9_998)));
+        let mut expected = -4_999..4_999;
+        while let (Some(el), Some(c_element)) = (iter.next(), expected.next()) {
+            assert_eq!(c_element, *el);
+            if let (Some(el), Some(c_element)) = (iter.next_back(), expected.next_back()) {
+                assert_eq!(c_element, *el);
+            }
+        }
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+    #[test]
+    fn test_iter_nth() {
+        let vec = gen_sorted_container(5_000, OrderType::Desc, true);
+        let mut iter = vec.iter();
+        assert_eq!(iter.nth(1), Some(&4_998));
+        assert_eq!(iter.nth(2_499), Some(&2_498));
+        assert_eq!(iter.nth_back(0), Some(&-5_000));
+        assert_eq!(iter.nth_back(4_000), Some(&-999));
+        assert_eq!(iter.len(), 10_000 - 2_502 - 4_002);
+        assert_eq!(iter.next(), Some(&2_497));
+        assert_eq!(iter.next_back(), Some(&-998));
+        assert_eq!(iter.nth(10_000), None);
+        assert_eq!(iter.next_back(), None);
+        for step in [1, 7, 999, 2_001] {
+            let expected: Vec<i32> = (-5_000..5_000).rev().step_by(step).collect();
+            assert_eq!(
+                vec.iter().step_by(step).copied().collect::<Vec<i32>>(),
+                expected
+            );
+        }
+        let mut range = vec.range_by_value((Bound::Included(100), Bound::Included(-100)));
+        assert_eq!(range.len(), 201);
+        assert_eq!(range.nth_back(100), Some(&0));
+        assert_eq!(range.nth(99), Some(&1));
+        assert_eq!(range.len(), 0);
+        let mut empty: SortedContainers<i32> = SortedContainers::default();
+        assert_eq!(empty.iter().next_back(), None);
+        assert!(empty.insert(1).is_ok());
+        empty.clear();
+        assert_eq!(empty.iter().len(), 0);
+    }
+    #[test]
+    fn test_into_iter() {
+        let mut vec = SortedContainers::new(OrderType::Desc);
+        for el in 0..10_000 {
+            assert!(vec.insert(el.to_string()).is_ok());
+        }
+        let mut expected: Vec<String> = (0..10_000).map(|el| el.to_string()).collect();
+        expected.sort_by(|a, b| b.cmp(a));
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 10_000);
+        assert_eq!(iter.next_back(), expected.pop());
+        assert_eq!(iter.next(), Some(expected.remove(0)));
+        assert_eq!(iter.len(), 9_998);
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        while let Some(el) = iter.next() {
+            front.push(el);
+            back.extend(iter.next_back());
+        }
+        front.extend(back.into_iter().rev());
+        assert_eq!(front, expected);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        let vec = gen_sorted_container(5_000, OrderType::Asc, true);
+        let owned: Vec<i32> = vec.into_iter().rev().collect();
+        assert_eq!(owned, (-5_000..5_000).rev().collect::<Vec<i32>>());
+        assert_eq!(SortedContainers::<i32>::default().into_iter().count(), 0);
+    }
+    #[test]
+    fn test_from_iter() {
+        let mut rng = thread_rng();
+        let mut elements: Vec<i32> = (0..100_000).map(|_| rng.gen_range(0..50_000)).collect();
+        let vec: SortedContainers<i32> = elements.iter().copied().collect();
+        elements.sort();
+        elements.dedup();
+        assert_eq!(vec.len(), elements.len());
+        assert_eq!(vec.iter().copied().collect::<Vec<i32>>(), elements);
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+        for i in 0..vec.data.len() {
+            assert_ge!(vec.data[i].len(), 500);
+            assert_le!(vec.data[i].len(), 1_000);
+        }
+        for (i, el) in elements.iter().enumerate() {
+            assert_eq!(vec.find(el), Some(i));
+        }
+    }
+    #[test]
+    fn test_from_sorted_vec() {
+        let elements: Vec<i32> = (-50_000..50_000).rev().collect();
+        let mut vec = SortedContainers::from_sorted_vec(OrderType::Desc, elements.clone());
+        assert_eq!(vec.len(), 100_000);
+        assert_eq!(vec.iter().copied().collect::<Vec<i32>>(), elements);
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+        assert!(vec.insert(-50_001).is_ok());
+        assert!(vec.insert(0).is_err());
+        assert_eq!(vec.last(), Some(&-50_001));
+        // the input is sorted if needed
+        let vec = SortedContainers::from_sorted_iter(OrderType::Asc, elements);
+        assert_eq!(vec[0], -50_000);
+        check_sub_vectors(&vec);
+        let vec = SortedContainers::<i32>::from_sorted_vec(OrderType::Asc, Vec::new());
+        assert!(vec.is_empty());
+        assert_eq!(vec.iter().count(), 0);
+    }
+    #[test]
+    fn test_extend() {
+        let mut vec = SortedContainers::new_with_strategies(
+            OrderType::Asc,
+            |len, _pos| len > 100,
+            |len, _pos| len < 25,
+        );
+        vec.extend((0..10_000).map(|el| el * 2));
+        for i in 0..vec.data.len() {
+            assert_ge!(vec.data[i].len(), 25);
+            assert_le!(vec.data[i].len(), 50);
+        }
+        // the collection is not empty, the elements are merged into the sub-vectors
+        vec.extend(0..20_000);
+        assert_eq!(vec.len(), 20_000);
+        assert_eq!(
+            vec.iter().copied().collect::<Vec<i32>>(),
+            (0..20_000).collect::<Vec<i32>>()
+        );
+        check_sub_vectors(&vec);
+        let mut vec = SortedContainers::new_multiset(OrderType::Asc);
+        vec.extend([(2, 'a'), (1, 'b'), (2, 'c')].map(|(value, tag)| Sample {
+            value,
+            seq: tag as usize,
+        }));
+        let tags: Vec<usize> = vec.iter().map(|sample| sample.seq).collect();
+        assert_eq!(tags, ['b', 'a', 'c'].map(|tag| tag as usize));
+    }
+    #[test]
+    fn test_insert_batch() {
+        let mut rng = thread_rng();
+        for order_type in [OrderType::Asc, OrderType::Desc] {
+            let mut vec = SortedContainers::new(order_type);
+            let mut expected = std::collections::BTreeSet::new();
+            for _ in 0..20 {
+                let batch_len = rng.gen_range(0..20_000);
+                let batch: Vec<i32> = (0..batch_len).map(|_| rng.gen_range(0..100_000)).collect();
+                let new_elements = batch.iter().filter(|el| expected.insert(**el)).count();
+                assert_eq!(
+                    vec.insert_batch(batch),
+                    (new_elements, batch_len - new_elements)
+                );
+                assert_eq!(vec.len(), expected.len());
+                check_sub_vectors(&vec);
+                test_index_check_trait(&vec);
+                for i in 0..vec.data.len() {
+                    assert_le!(vec.data[i].len(), 2_000);
+                }
+            }
+            let elements: Vec<i32> = match order_type {
+                OrderType::Asc => expected.iter().copied().collect(),
+                OrderType::Desc => expected.iter().rev().copied().collect(),
+            };
+            assert_eq!(vec.iter().copied().collect::<Vec<i32>>(), elements);
+        }
+    }
+    #[test]
+    fn test_insert_batch_multiset() {
+        let mut vec = SortedContainers::new_multiset(OrderType::Asc);
+        vec.extend((0..3_000).map(|seq| Sample {
+            value: (seq % 10) as i32,
+            seq,
+        }));
+        let batch = (3_000..6_000).map(|seq| Sample {
+            value: (seq % 11) as i32,
+            seq,
+        });
+        assert_eq!(vec.insert_batch(batch), (3_000, 0));
+        assert_eq!(vec.len(), 6_000);
+        for i in 1..vec.len() {
+            assert_le!(vec[i - 1].value, vec[i].value);
+            if vec[i - 1].value == vec[i].value {
+                // the elements inserted by the batch follow the equal ones already stored
+                assert_lt!(vec[i - 1].seq, vec[i].seq);
+            }
+        }
+        assert_eq!(vec.count(&Sample { value: 10, seq: 0 }), 273);
+        assert_eq!(vec.insert_batch(Vec::new()), (0, 0));
+    }
+    #[test]
+    fn test_set_operations() {
+        let evens = SortedContainers::from_sorted_iter(OrderType::Asc, (0..10_000).step_by(2));
+        let threes = SortedContainers::from_sorted_iter(OrderType::Desc, (0..10_000).step_by(3));
+        let collect = |iter: &mut dyn Iterator<Item = &i32>| iter.copied().collect::<Vec<i32>>();
+        let union: Vec<i32> = (0..10_000)
+            .filter(|el| el % 2 == 0 || el % 3 == 0)
+            .collect();
+        let intersection: Vec<i32> = (0..10_000).step_by(6).collect();
+        let difference: Vec<i32> = (0..10_000)
+            .filter(|el| el % 2 == 0 && el % 3 != 0)
+            .collect();
+        let symmetric: Vec<i32> = (0..10_000)
+            .filter(|el| (el % 2 == 0) != (el % 3 == 0))
+            .collect();
+        assert_eq!(collect(&mut evens.union(&threes)), union);
+        assert_eq!(collect(&mut evens.intersection(&threes)), intersection);
+        assert_eq!(collect(&mut evens.difference(&threes)), difference);
+        assert_eq!(collect(&mut evens.symmetric_difference(&threes)), symmetric);
+        // the result follows the order of the first collection
+        let reversed: Vec<i32> = intersection.iter().rev().copied().collect();
+        assert_eq!(collect(&mut threes.intersection(&evens)), reversed);
+        assert_eq!(
+            (&evens | &threes).iter().copied().collect::<Vec<i32>>(),
+            union
+        );
+        assert_eq!(
+            (&evens & &threes).iter().copied().collect::<Vec<i32>>(),
+            intersection
+        );
+        assert_eq!(
+            (&evens - &threes).iter().copied().collect::<Vec<i32>>(),
+            difference
+        );
+        assert_eq!(
+            (&evens ^ &threes).iter().copied().collect::<Vec<i32>>(),
+            symmetric
+        );
+        let mut result = &threes & &evens;
+        assert_eq!(result.iter().copied().collect::<Vec<i32>>(), reversed);
+        check_sub_vectors(&result);
+        assert!(result.insert(6).is_err());
+        assert!(result.insert(10_002).is_ok());
+        assert_eq!(result.first(), Some(&10_002));
+    }
+    #[test]
+    fn test_set_comparisons() {
+        let evens = SortedContainers::from_sorted_iter(OrderType::Asc, (0..1_000).step_by(2));
+        let sixes = SortedContainers::from_sorted_iter(OrderType::Desc, (0..1_000).step_by(6));
+        let odds = SortedContainers::from_sorted_iter(OrderType::Desc, (1..1_000).step_by(2));
+        let empty = SortedContainers::default();
+        assert!(sixes.is_subset(&evens));
+        assert!(!evens.is_subset(&sixes));
+        assert!(evens.is_superset(&sixes));
+        assert!(!sixes.is_superset(&evens));
+        assert!(evens.is_disjoint(&odds));
+        assert!(!evens.is_disjoint(&sixes));
+        assert!(empty.is_subset(&evens));
+        assert!(evens.is_superset(&empty));
+        assert!(empty.is_disjoint(&empty));
+        assert!(evens.is_subset(&evens));
+    }
+    #[test]
+    fn test_set_operations_multiset() {
+        let mut a = SortedContainers::new_multiset(OrderType::Asc);
+        a.extend([1, 1, 1, 2, 3]);
+        let mut b = SortedContainers::new_multiset(OrderType::Desc);
+        b.extend([1, 2, 2, 4]);
+        let collect = |iter: &mut dyn Iterator<Item = &i32>| iter.copied().collect::<Vec<i32>>();
+        assert_eq!(collect(&mut a.union(&b)), [1, 1, 1, 2, 2, 3, 4]);
+        assert_eq!(collect(&mut a.intersection(&b)), [1, 2]);
+        assert_eq!(collect(&mut a.difference(&b)), [1, 1, 3]);
+        assert_eq!(collect(&mut a.symmetric_difference(&b)), [1, 1, 2, 3, 4]);
+        let union = &a | &b;
+        assert!(union.is_multiset());
+        assert_eq!(union.count(&1), 3);
+    }
+    #[test]
+    fn test_split_off() {
+        let mut rng = thread_rng();
+        for order_type in [OrderType::Asc, OrderType::Desc] {
+            let mut vec = gen_sorted_container(50_000, order_type, true);
+            let mut expected: Vec<i32> = vec.iter().copied().collect();
+            while vec.len() > 1 {
+                let index = rng.gen_range(0..=vec.len());
+                let other = vec.split_off_at(index);
+                let other_expected = expected.split_off(index);
+                for (vec, expected) in [(&vec, &expected), (&other, &other_expected)] {
+                    assert_eq!(vec.len(), expected.len());
+                    assert_eq!(vec.iter().copied().collect::<Vec<i32>>(), *expected);
+                    if !vec.is_empty() {
+                        check_sub_vectors(vec);
+                        test_index_check_trait(vec);
+                    }
+                }
+                if other.len() > vec.len() {
+                    vec = other;
+                    expected = other_expected;
+                }
+            }
+        }
+        let mut vec = gen_sorted_container(5_000, OrderType::Desc, false);
+        let other = vec.split_off(&0);
+        assert_eq!(vec.len(), 4_999);
+        assert_eq!(vec.last(), Some(&1));
+        assert_eq!(other.len(), 5_001);
+        assert_eq!(other.first(), Some(&0));
+        let other = vec.split_off(&-1);
+        assert!(other.is_empty());
+        let mut other = vec.split_off_at(0);
+        assert!(vec.is_empty());
+        assert!(other.insert(10_000).is_ok());
+        assert_eq!(other.first(), Some(&10_000));
+    }
+    #[test]
+    fn test_append() {
+        let mut low = SortedContainers::from_sorted_iter(OrderType::Asc, 0..50_000);
+        let mut high = SortedContainers::from_sorted_iter(OrderType::Asc, 50_000..100_000);
+        let depth = low.depth() + high.depth();
+        low.append(&mut high);
+        assert!(high.is_empty());
+        assert_eq!(low.len(), 100_000);
+        assert_eq!(low.depth(), depth);
+        check_sub_vectors(&low);
+        test_index_check_trait(&low);
+        // the other collection precedes this one
+        let mut lowest = SortedContainers::from_sorted_iter(OrderType::Asc, -50_000..0);
+        lowest.append(&mut low);
+        assert_eq!(lowest.len(), 150_000);
+        assert_eq!(
+            lowest.iter().copied().collect::<Vec<i32>>(),
+            (-50_000..100_000).collect::<Vec<i32>>()
+        );
+        check_sub_vectors(&lowest);
+        test_index_check_trait(&lowest);
+        assert!(high.insert(42).is_ok());
+        assert_eq!(high.len(), 1);
+        // small collections are merged at the junction instead of being piled up
+        let mut vec = SortedContainers::default();
+        for start in (0..10_000).step_by(10) {
+            let mut small = SortedContainers::from_sorted_iter(OrderType::Asc, start..start + 10);
+            vec.append(&mut small);
+        }
+        assert!(vec.iter().copied().eq(0..10_000));
+        assert_le!(vec.depth(), 10_000 / 500);
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+    }
+    #[test]
+    fn test_append_multiset_into_set() {
+        let mut set = SortedContainers::from_sorted_vec(OrderType::Asc, vec![1, 2]);
+        let mut multiset = SortedContainers::new_multiset(OrderType::Asc);
+        multiset.extend([3, 3, 3]);
+        set.append(&mut multiset);
+        assert!(multiset.is_empty());
+        assert_eq!(set.iter().copied().collect::<Vec<i32>>(), [1, 2, 3]);
+        assert_eq!(set.count(&3), 1);
+        check_sub_vectors(&set);
+        // a multiset accepts the elements of another multiset as they are
+        let mut other = SortedContainers::new_multiset(OrderType::Asc);
+        other.extend([3, 4, 4]);
+        multiset.extend([1, 3]);
+        multiset.append(&mut other);
+        assert_eq!(
+            multiset.iter().copied().collect::<Vec<i32>>(),
+            [1, 3, 3, 4, 4]
+        );
+    }
+    #[test]
+    fn test_append_overlapping() {
+        let mut evens = SortedContainers::from_sorted_iter(OrderType::Asc, (0..10_000).step_by(2));
+        let mut threes =
+            SortedContainers::from_sorted_iter(OrderType::Desc, (0..10_000).step_by(3));
+        evens.append(&mut threes);
+        assert!(threes.is_empty());
+        let expected: Vec<i32> = (0..10_000)
+            .filter(|el| el % 2 == 0 || el % 3 == 0)
+            .collect();
+        assert_eq!(evens.iter().copied().collect::<Vec<i32>>(), expected);
+        check_sub_vectors(&evens);
+        // a disjoint collection sorted in the opposite direction is merged
+        let mut vec = SortedContainers::from_sorted_iter(OrderType::Asc, 0..100);
+        let mut other = SortedContainers::from_sorted_iter(OrderType::Desc, 100..200);
+        vec.append(&mut other);
+        assert_eq!(
+            vec.iter().copied().collect::<Vec<i32>>(),
+            (0..200).collect::<Vec<i32>>()
+        );
+        let mut empty = SortedContainers::default();
+        empty.append(&mut vec);
+        assert_eq!(empty.len(), 200);
+        assert!(vec.is_empty());
+        check_sub_vectors(&empty);
+        test_index_check_trait(&empty);
+    }
+    #[test]
+    fn test_nearest_neighbours() {
+        let mut vec = SortedContainers::default();
+        vec.extend((0..20_000).step_by(10));
+        for el in -5..20_010 {
+            let floor = (el >= 0).then(|| (el.min(19_990) / 10 * 10) as usize);
+            let ceiling = (el <= 19_990).then(|| ((el.max(0) + 9) / 10 * 10) as usize);
+            assert_eq!(
+                vec.floor(&el),
+                floor.map(|value| (value / 10, &vec[value / 10]))
+            );
+            assert_eq!(
+                vec.ceiling(&el),
+                ceiling.map(|value| (value / 10, &vec[value / 10]))
+            );
+            if el % 10 != 0 {
+                assert_eq!(vec.lower(&el), vec.floor(&el));
+                assert_eq!(vec.higher(&el), vec.ceiling(&el));
+            }
+        }
+        assert_eq!(vec.floor(&42), Some((4, &40)));
+        assert_eq!(vec.lower(&40), Some((3, &30)));
+        assert_eq!(vec.higher(&40), Some((5, &50)));
+        assert_eq!(vec.lower(&0), None);
+        assert_eq!(vec.higher(&19_990), None);
+        assert_eq!(vec.floor(&19_990), Some((1_999, &19_990)));
+        let distance = |a: &i32, b: &i32| (a - b).abs();
+        assert_eq!(vec.closest_by(&44, distance), Some((4, &40)));
+        assert_eq!(vec.closest_by(&45, distance), Some((4, &40)));
+        assert_eq!(vec.closest_by(&46, distance), Some((5, &50)));
+        assert_eq!(vec.closest_by(&-100, distance), Some((0, &0)));
+        assert_eq!(vec.closest_by(&100_000, distance), Some((1_999, &19_990)));
+        assert_eq!(SortedContainers::default().closest_by(&0, distance), None);
+    }
+    #[test]
+    fn test_nearest_neighbours_desc() {
+        let mut vec = SortedContainers::new_multiset(OrderType::Desc);
+        vec.extend([50, 40, 40, 30]);
+        // the neighbours are compared by value, the nearest equal element is returned
+        assert_eq!(vec.floor(&45), Some((1, &40)));
+        assert_eq!(vec.ceiling(&45), Some((0, &50)));
+        assert_eq!(vec.floor(&40), Some((1, &40)));
+        assert_eq!(vec.ceiling(&40), Some((2, &40)));
+        assert_eq!(vec.lower(&40), Some((3, &30)));
+        assert_eq!(vec.higher(&40), Some((0, &50)));
+        assert_eq!(vec.higher(&50), None);
+        assert_eq!(vec.lower(&30), None);
+        assert_eq!(vec.floor(&20), None);
+        assert_eq!(vec.floor(&60), Some((0, &50)));
+        assert_eq!(vec.ceiling(&60), None);
+        assert_eq!(
+            vec.closest_by(&33, |a: &i32, b: &i32| (a - b).abs()),
+            Some((3, &30))
+        );
+        let vec = SortedContainers::from_sorted_iter(OrderType::Desc, (1..=100).rev());
+        assert_eq!(vec.floor(&0), None);
+        assert_eq!(vec.floor(&1), Some((99, &1)));
+        assert_eq!(vec.ceiling(&0), Some((99, &1)));
+        assert_eq!(vec.higher(&42), Some((57, &43)));
+    }
+    #[test]
+    fn test_entry() {
+        let mut vec = SortedContainers::new(OrderType::Desc);
+        let mut elements: Vec<i32> = (0..10_000).collect();
+        elements.shuffle(&mut thread_rng());
+        for element in elements.iter() {
+            let entry = vec.entry(element);
+            assert!(matches!(entry, Entry::Vacant(_)));
+            assert_eq!(*entry.or_insert(*element), *element);
+        }
+        assert_eq!(vec.len(), 10_000);
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+        assert!(vec.iter().copied().eq((0..10_000).rev()));
+        match vec.entry(&4_000) {
+            Entry::Occupied(entry) => {
+                assert_eq!(entry.index(), 5_999);
+                assert_eq!(entry.get(), &4_000);
+                assert_eq!(entry.remove_entry(), 4_000);
+            }
+            Entry::Vacant(_) => panic!("the element must be found"),
+        }
+        let entry = vec.entry(&4_000);
+        assert_eq!(entry.index(), 5_999);
+        assert_eq!(*entry.or_insert_with(|| 4_000), 4_000);
+        assert_eq!(vec.find(&4_000), Some(5_999));
+        check_sub_vectors(&vec);
+    }
+    #[test]
+    fn test_entry_and_modify() {
+        let mut words =
+            SortedContainers::new_with_comparator(|a: &(String, usize), b: &(String, usize)| {
+                a.0.cmp(&b.0)
+            });
+        for word in ["pear", "apple", "pear", "fig", "pear", "apple"] {
+            words
+                .entry(&(word.to_string(), 0))
+                .and_modify(|(_, count)| *count += 1)
+                .or_insert_with(|| (word.to_string(), 1));
+        }
+        let counts: Vec<(&str, usize)> = words
+            .iter()
+            .map(|(word, count)| (word.as_str(), *count))
+            .collect();
+        assert_eq!(counts, [("apple", 2), ("fig", 1), ("pear", 3)]);
+    }
+    #[test]
+    fn test_entry_unequal_element() {
+        let mut vec: SortedContainers<i32> = [0, 10].into_iter().collect();
+        // the element differs from the searched one but sorts into the same position
+        assert_eq!(*vec.entry(&3).or_insert(7), 7);
+        assert!(vec.iter().copied().eq([0, 7, 10]));
+    }
+    #[test]
+    #[should_panic(expected = "the element does not belong to the entry position")]
+    fn test_entry_wrong_element() {
+        let mut vec: SortedContainers<i32> = (0..10).map(|i| i * 2).collect();
+        vec.entry(&5).or_insert(9);
+    }
 
     fn test_index_check_trait(vec: &SortedContainers<i32>) {
         let mut idx = 0;
@@ -612,20 +2344,386 @@ mod test {
             pos += 1;
         }
     }
-    fn check_maxes(vec: &SortedContainers<i32>, order_type: OrderType) {
-        for i in 0..vec.data.len() {
-            match order_type {
-                OrderType::Asc => {
-                    let last_el = vec.data[i].last().unwrap();
-                    assert_eq!(*last_el, vec.maxes[i]);
+    #[test]
+    fn test_non_clone_elements() {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Heavy(Vec<u32>);
+
+        let mut elements: Vec<u32> = (0..10_000).collect();
+        elements.shuffle(&mut thread_rng());
+        let mut vec = SortedContainers::new(OrderType::Desc);
+        for element in elements {
+            assert!(vec.insert(Heavy(vec![element])).is_ok());
+        }
+        assert!(vec.insert(Heavy(vec![42])).is_err());
+        check_sub_vectors_by(&vec, |a, b| b.cmp(a));
+        assert_eq!(vec.find(&Heavy(vec![9_000])), Some(999));
+        assert_eq!(vec.remove(&Heavy(vec![9_000])), Some(Heavy(vec![9_000])));
+        assert_eq!(vec.replace(Heavy(vec![7])), Some(Heavy(vec![7])));
+        let mut low = vec.split_off(&Heavy(vec![4_999]));
+        assert_eq!(low.first(), Some(&Heavy(vec![4_999])));
+        assert_eq!(low.drain(..1_000).count(), 1_000);
+        vec.append(&mut low);
+        assert_eq!(vec.len(), 8_999);
+        check_sub_vectors_by(&vec, |a, b| b.cmp(a));
+        assert!(vec
+            .into_iter()
+            .map(|Heavy(values)| values[0])
+            .eq((0..10_000)
+                .rev()
+                .filter(|element| *element != 9_000 && !(4_000..=4_999).contains(element))));
+    }
+    #[test]
+    fn test_panicking_comparator() {
+        type Operation<'a> = &'a dyn Fn(&mut SortedContainers<i32>, &mut Vec<i32>);
+        // the comparator panics when the countdown reaches zero
+        let countdown = Arc::new(AtomicUsize::new(usize::MAX));
+        let comparator_countdown = Arc::clone(&countdown);
+        let mut vec = SortedContainers::new_with_comparator(move |a: &i32, b: &i32| {
+            if comparator_countdown.fetch_sub(1, AtomicOrdering::Relaxed) == 1 {
+                panic!("comparator panic");
+            }
+            a.cmp(b)
+        });
+        vec.extend((0..20_000).map(|i| i * 2));
+        let mut expected: Vec<i32> = vec.iter().copied().collect();
+        let mut rng = thread_rng();
+        for comparisons in [1, 2, 7, 30, 100, 1_000, 10_000, 50_000] {
+            let batch: Vec<i32> = (0..5_000).map(|_| rng.gen_range(-100..50_000)).collect();
+            let value = rng.gen_range(-100..50_000);
+            let operations: [Operation; 4] = [
+                &|vec, expected| {
+                    vec.insert_batch(batch.clone());
+                    expected.extend(batch.iter().copied());
+                },
+                &|vec, expected| {
+                    if vec.insert(value).is_ok() {
+                        expected.push(value);
+                    }
+                },
+                &|vec, expected| {
+                    if vec.remove(&value).is_some() {
+                        expected.retain(|element| *element != value);
+                    }
+                },
+                &|vec, expected| {
+                    vec.entry(&value).or_insert(value);
+                    expected.push(value);
+                },
+            ];
+            for operation in operations {
+                countdown.store(comparisons, AtomicOrdering::Relaxed);
+                let mut result = expected.clone();
+                let completed =
+                    catch_unwind(AssertUnwindSafe(|| operation(&mut vec, &mut result))).is_ok();
+                countdown.store(usize::MAX, AtomicOrdering::Relaxed);
+                if completed {
+                    result.sort();
+                    result.dedup();
+                    expected = result;
                 }
-                OrderType::Desc => {
-                    let first_el = vec.data[i].first().unwrap();
-                    assert_eq!(*first_el, vec.maxes[i]);
+                // a panicking comparator leaves the collection unchanged
+                assert_eq!(vec.len(), vec.data.iter().map(|values| values.len()).sum());
+                assert!(vec.iter().eq(expected.iter()));
+                check_sub_vectors(&vec);
+                test_index_check_trait(&vec);
+            }
+        }
+    }
+    #[test]
+    fn test_panicking_comparator_multiset() {
+        let panic = Arc::new(AtomicUsize::new(0));
+        let comparator_panic = Arc::clone(&panic);
+        let mut vec = SortedContainers::new_multiset(OrderType::Asc);
+        vec.extend((0..10_000).map(|i| i / 3));
+        let mut vec = SortedContainers {
+            comparator: Arc::new(move |a: &i32, b: &i32| {
+                if comparator_panic.load(AtomicOrdering::Relaxed) == 1 && a == b {
+                    panic!("comparator panic");
+                }
+                a.cmp(b)
+            }),
+            ..vec
+        };
+        let expected: Vec<i32> = vec.iter().copied().collect();
+        panic.store(1, AtomicOrdering::Relaxed);
+        assert!(catch_unwind(AssertUnwindSafe(|| vec.insert_batch(0..3_000))).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| vec.remove_all(&1_000))).is_err());
+        panic.store(0, AtomicOrdering::Relaxed);
+        assert!(vec.iter().eq(expected.iter()));
+        check_sub_vectors(&vec);
+        test_index_check_trait(&vec);
+        assert_eq!(vec.insert_batch(0..3_000), (3_000, 0));
+        assert_eq!(vec.count(&1_000), 4);
+    }
+    #[test]
+    fn test_panicking_comparator_each_comparison() {
+        type Operation = fn(&mut SortedContainers<String>);
+        // the comparator panics when the countdown reaches zero
+        let countdown = Arc::new(AtomicUsize::new(usize::MAX));
+        let comparator_countdown = Arc::clone(&countdown);
+        let comparator = move |a: &String, b: &String| {
+            if comparator_countdown.fetch_sub(1, AtomicOrdering::Relaxed) == 1 {
+                panic!("comparator panic");
+            }
+            a.cmp(b)
+        };
+        // a small load factor makes every insertion expand and every removal shrink a sub-vector
+        let build = |multiset: bool| {
+            let elements = (0..16).map(|i| format!("{:02}", i * 2));
+            let copies = if multiset { 2 } else { 1 };
+            SortedContainers::builder()
+                .comparator(comparator.clone())
+                .multiset(multiset)
+                .load_factor(2)
+                .build_from(elements.flat_map(|element| vec![element; copies]))
+        };
+        let operations: [Operation; 10] = [
+            |vec| assert!(vec.insert("07".to_string()).is_ok()),
+            |vec| assert!(vec.update("08".to_string()).is_ok()),
+            |vec| assert!(vec.insert_or_update("09".to_string()).is_ok()),
+            |vec| assert!(vec.replace("10".to_string()).is_some()),
+            |vec| assert!(vec.remove(&"12".to_string()).is_some()),
+            |vec| assert!(vec.remove(&"00".to_string()).is_some()),
+            |vec| assert_eq!(vec.insert_batch(["31", "03", "14"].map(String::from)).0, 2),
+            |vec| {
+                assert_eq!(
+                    *vec.entry(&"05".to_string()).or_insert("05".to_string()),
+                    "05"
+                )
+            },
+            |vec| match vec.entry(&"16".to_string()) {
+                Entry::Occupied(mut entry) => assert_eq!(entry.insert("16".to_string()), "16"),
+                Entry::Vacant(_) => panic!("the element must be found"),
+            },
+            |vec| match vec.entry(&"18".to_string()) {
+                Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), "18"),
+                Entry::Vacant(_) => panic!("the element must be found"),
+            },
+        ];
+        let multiset_operations: [Operation; 3] = [
+            |vec| assert!(vec.insert("20".to_string()).is_ok()),
+            |vec| assert_eq!(vec.remove_all(&"22".to_string()), 2),
+            |vec| assert!(vec.remove(&"30".to_string()).is_some()),
+        ];
+        let cases = operations.iter().map(|operation| (false, operation));
+        let multiset_cases = multiset_operations
+            .iter()
+            .map(|operation| (true, operation));
+        for (multiset, operation) in cases.chain(multiset_cases) {
+            let original = build(multiset);
+            let mut expected = build(multiset);
+            operation(&mut expected);
+            // the comparator panics at every comparison in turn until the operation completes
+            for comparisons in 1.. {
+                let mut vec = build(multiset);
+                countdown.store(comparisons, AtomicOrdering::Relaxed);
+                let completed = catch_unwind(AssertUnwindSafe(|| operation(&mut vec))).is_ok();
+                countdown.store(usize::MAX, AtomicOrdering::Relaxed);
+                // a panicking comparator leaves the collection unchanged
+                let expected = if completed { &expected } else { &original };
+                assert!(vec.iter().eq(expected.iter()));
+                assert_eq!(vec.len(), vec.data.iter().map(|values| values.len()).sum());
+                assert!((0..vec.len())
+                    .map(|i| &vec[i])
+                    .eq(vec.data.iter().flatten()));
+                check_sub_vectors_by(&vec, String::cmp);
+                if completed {
+                    break;
                 }
             }
         }
     }
+    #[test]
+    fn test_builder() {
+        let mut vec = SortedContainers::builder()
+            .order(OrderType::Desc)
+            .multiset(true)
+            .load_factor(100)
+            .with_capacity(10_000)
+            .build();
+        assert!(vec.is_multiset());
+        assert_ge!(vec.data.capacity(), 100);
+        let mut elements: Vec<i32> = (0..10_000).map(|i| i / 2).collect();
+        elements.shuffle(&mut thread_rng());
+        for element in elements {
+            assert!(vec.insert(element).is_ok());
+        }
+        assert!(vec.iter().copied().eq((0..10_000).rev().map(|i| i / 2)));
+        check_sub_vectors(&vec);
+        // sub-vectors are splitted above 200 elements and merged below 50 elements
+        for i in 0..vec.data.len() {
+            assert_ge!(vec.data[i].len(), 50);
+            assert_le!(vec.data[i].len(), 200);
+        }
+        for element in 0..4_900 {
+            vec.remove_all(&element);
+        }
+        assert_eq!(vec.len(), 200);
+        assert_le!(vec.depth(), 4);
+        let vec = SortedContainers::builder()
+            .comparator(|a: &i32, b: &i32| (a % 10).cmp(&(b % 10)))
+            .build_from(0..100);
+        assert!(vec.iter().copied().eq(0..10));
+        // reserving room doesn't make the policy rebuild the sub-vectors on the first insertion
+        let mut vec = SortedContainers::builder()
+            .rebalance_policy(AdaptiveLoadFactor::new())
+            .with_capacity(1_000_000)
+            .build();
+        assert!(vec.insert(1).is_ok());
+        assert_ge!(vec.data[0].capacity(), 4_000);
+    }
+    #[test]
+    fn test_builder_strategies() {
+        let max_len = 64;
+        let mut vec = SortedContainers::builder()
+            .rebalance_policy(StrategyPolicy::new(
+                move |context: &RebalanceContext| context.len > max_len,
+                |context: &RebalanceContext| {
+                    context.len < 8 && context.previous_len.is_some_and(|len| len < 32)
+                },
+            ))
+            .build();
+        vec.extend(0..10_000);
+        for element in 0..10_000 {
+            assert!(vec.insert(element * 2 + 20_000).is_ok());
+        }
+        check_sub_vectors(&vec);
+        assert!(vec.data.iter().all(|values| values.len() <= max_len));
+        // the policy is inherited by the derived collections
+        let mut split = vec.split_off_at(5_000);
+        assert_eq!(split.len(), 15_000);
+        assert!(split.insert_batch(30_001..40_000).0 > 0);
+        check_sub_vectors(&split);
+        assert!(split.data.iter().all(|values| values.len() <= max_len));
+    }
+    #[test]
+    fn test_never_shrinking_policy() {
+        // a sub-vector emptied by the removals is dropped even if the policy never shrinks
+        let never_shrinking = [
+            SortedContainers::new_with_strategies(OrderType::Asc, |len, _| len > 4, |_, _| false),
+            SortedContainers::builder().load_factor(1).build(),
+        ];
+        for mut vec in never_shrinking {
+            vec.extend(0..20);
+            for element in 4..12 {
+                assert_eq!(vec.remove(&element), Some(element));
+            }
+            assert!(vec.data.iter().all(|values| !values.is_empty()));
+            assert_eq!(vec.find(&12), Some(4));
+            assert_eq!(vec.find(&3), Some(3));
+            assert_eq!(vec.find(&8), None);
+            assert_eq!(vec.insert_batch([100, 101, 6]), (3, 0));
+            assert!(vec
+                .iter()
+                .copied()
+                .eq((0..4).chain([6]).chain(12..20).chain([100, 101])));
+            check_sub_vectors(&vec);
+            test_index_check_trait(&vec);
+        }
+    }
+    #[test]
+    fn test_adaptive_policy() {
+        let mut vec = SortedContainers::builder()
+            .rebalance_policy(AdaptiveLoadFactor::new())
+            .build();
+        let mut elements: Vec<i32> = (0..200_000).collect();
+        elements.shuffle(&mut thread_rng());
+        // the number of sub-vectors stays close to len / (4 * sqrt(len))
+        let check_depth = |vec: &SortedContainers<i32>| {
+            let load_factor = (4 * vec.len().isqrt()).max(32);
+            assert_le!(vec.depth(), 4 * vec.len() / load_factor + 1);
+            assert_ge!(vec.depth(), vec.len() / (4 * load_factor));
+        };
+        for (i, element) in elements.iter().enumerate() {
+            assert!(vec.insert(*element).is_ok());
+            if i % 1_000 == 0 {
+                check_depth(&vec);
+            }
+        }
+        check_sub_vectors(&vec);
+        check_depth(&vec);
+        assert!(vec.iter().copied().eq(0..200_000));
+        for (i, element) in elements.iter().enumerate().take(199_000) {
+            assert_eq!(vec.remove(element), Some(*element));
+            if i % 1_000 == 0 {
+                check_depth(&vec);
+            }
+        }
+        check_sub_vectors(&vec);
+        check_depth(&vec);
+        test_index_check_trait(&vec);
+        // bulk changes rebuild the sub-vectors as well
+        vec.insert_batch(-100_000..0);
+        check_depth(&vec);
+        vec.remove_range(1_000..);
+        check_depth(&vec);
+        check_sub_vectors(&vec);
+        assert!(vec.iter().copied().eq(-100_000..-99_000));
+    }
+    #[derive(Clone, Default)]
+    struct CountingPolicy {
+        expansions: usize,
+        shrinks: usize,
+    }
+    impl RebalancePolicy for CountingPolicy {
+        fn should_expand(&mut self, context: &RebalanceContext) -> bool {
+            assert_eq!(context.previous_len.is_some(), context.pos > 0);
+            assert_eq!(context.next_len.is_some(), context.pos + 1 < context.depth);
+            // the policy splits the first three sub-vectors only
+            let expand = context.len > 100 && self.expansions < 3;
+            self.expansions += expand as usize;
+            expand
+        }
+        fn should_shrink(&mut self, context: &RebalanceContext) -> bool {
+            let shrink = context.len < 10;
+            self.shrinks += shrink as usize;
+            shrink
+        }
+        fn load_factor(&self, _total_len: usize) -> usize {
+            50
+        }
+    }
+    #[test]
+    fn test_stateful_policy() {
+        let mut vec = SortedContainers::builder()
+            .rebalance_policy(CountingPolicy::default())
+            .build();
+        for element in 0..1_000 {
+            assert!(vec.insert(element).is_ok());
+        }
+        // sequential insertions split the last sub-vector until the policy stops expanding
+        assert_eq!(vec.depth(), 4);
+        assert_eq!(vec.data[3].len(), 1_000 - 3 * 50);
+        assert!(vec.iter().copied().eq(0..1_000));
+        while vec.len() > 900 {
+            vec.pop_first();
+        }
+        check_sub_vectors(&vec);
+        assert_eq!(vec.depth(), 2);
+        // the derived collections start from a copy of the policy state
+        let mut split = vec.split_off_at(0);
+        split.extend(1_000..2_000);
+        assert_eq!(split.depth(), 2);
+        assert!(split.iter().copied().eq(100..2_000));
+    }
+    fn check_sub_vectors(vec: &SortedContainers<i32>) {
+        let comparator = vec.comparator.clone();
+        check_sub_vectors_by(vec, |a, b| comparator.compare(a, b));
+    }
+    fn check_sub_vectors_by<T: Ord + std::fmt::Debug>(
+        vec: &SortedContainers<T>,
+        comparator: impl Fn(&T, &T) -> Ordering,
+    ) {
+        // every sub-vector is not empty and its last element precedes the first one of the next
+        for i in 0..vec.data.len() {
+            assert!(!vec.data[i].is_empty());
+            if i > 0 {
+                let prev_el = vec.data[i - 1].last().unwrap();
+                assert_ne!(comparator(prev_el, &vec.data[i][0]), Ordering::Greater);
+            }
+        }
+    }
     fn gen_sorted_container(
         len: usize,
         order_type: OrderType,
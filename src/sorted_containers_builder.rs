@@ -0,0 +1,88 @@
+use crate::comparator::Comparator;
+use crate::rebalance_policy::{FixedLoadFactor, RebalancePolicy};
+use crate::sorted_containers::{OrderType, SortedContainers};
+use std::sync::Arc;
+
+/// A builder to configure a new `SortedContainers<T>`, constructed by
+/// `SortedContainers::builder`.
+///
+/// By default the collection is ascending, rejects duplicates and uses the `FixedLoadFactor`
+/// policy with a load factor of 1000.
+///
+/// # Examples
+/// let sorted_containers = SortedContainers::builder()
+///     .comparator(|a: &String, b: &String| a.len().cmp(&b.len()))
+///     .multiset(true)
+///     .load_factor(load_factor_from_env())
+///     .build();
+pub struct SortedContainersBuilder<T> {
+    comparator: Arc<dyn Comparator<T>>,
+    allow_duplicates: bool,
+    policy: Box<dyn RebalancePolicy>,
+    capacity: usize,
+}
+impl<T: Ord> Default for SortedContainersBuilder<T> {
+    fn default() -> Self {
+        SortedContainersBuilder::new()
+    }
+}
+impl<T: Ord> SortedContainersBuilder<T> {
+    /// Constructs a new builder with the default configuration.
+    pub fn new() -> SortedContainersBuilder<T> {
+        SortedContainersBuilder {
+            comparator: Arc::new(OrderType::Asc),
+            allow_duplicates: false,
+            policy: Box::new(FixedLoadFactor::default()),
+            capacity: 0,
+        }
+    }
+    /// Sort the elements in ascending or descending order, replacing the comparator.
+    pub fn order(self, order_type: OrderType) -> Self {
+        self.comparator(order_type)
+    }
+    /// Sort the elements with the comparator in input. See
+    /// `SortedContainers::new_with_comparator`.
+    pub fn comparator<C: Comparator<T> + 'static>(mut self, comparator: C) -> Self {
+        self.comparator = Arc::new(comparator);
+        self
+    }
+    /// Accept elements equal to the ones already stored. See `SortedContainers::new_multiset`.
+    pub fn multiset(mut self, allow_duplicates: bool) -> Self {
+        self.allow_duplicates = allow_duplicates;
+        self
+    }
+    /// Keep the sub-vectors between half and twice the load factor in input, replacing the
+    /// rebalance policy. See `FixedLoadFactor`.
+    ///
+    /// Panics if the load factor is 0.
+    pub fn load_factor(self, load_factor: usize) -> Self {
+        self.rebalance_policy(FixedLoadFactor::new(load_factor))
+    }
+    /// Split and merge the sub-vectors following the policy in input.
+    pub fn rebalance_policy<P: RebalancePolicy + 'static>(mut self, policy: P) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+    /// Reserve room for at least `capacity` elements, so that the first sub-vector and the list
+    /// of sub-vectors are not reallocated while the collection is filled.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+    /// Constructs the configured empty collection.
+    pub fn build(self) -> SortedContainers<T> {
+        SortedContainers::from_parts(
+            self.comparator,
+            self.allow_duplicates,
+            self.policy,
+            self.capacity,
+        )
+    }
+    /// Constructs the configured collection containing the elements of the iterator in input.
+    /// See `SortedContainers::from_sorted_iter`.
+    pub fn build_from<I: IntoIterator<Item = T>>(self, iter: I) -> SortedContainers<T> {
+        let mut sorted_containers = self.build();
+        sorted_containers.extend(iter);
+        sorted_containers
+    }
+}
@@ -0,0 +1,313 @@
+use crate::comparator::Comparator;
+use crate::errors::SortedContainersError;
+use crate::rebalance_policy::{FixedLoadFactor, RebalanceContext, RebalancePolicy, StrategyPolicy};
+use crate::sorted_container_iter::SortedContainerIter;
+use crate::sorted_containers::{OrderType, SortedContainers};
+use std::cmp::Ordering;
+use std::ops::Index;
+use std::sync::Arc;
+
+/// Compares two elements by the keys extracted from them, following the order type.
+struct KeyComparator<F> {
+    key: Arc<F>,
+    order_type: OrderType,
+}
+impl<T, K: Ord, F: Fn(&T) -> K + Send + Sync> Comparator<T> for KeyComparator<F> {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.order_type.compare(&(self.key)(a), &(self.key)(b))
+    }
+    fn is_descending(&self) -> bool {
+        self.order_type == OrderType::Desc
+    }
+}
+/// A sorted collection whose elements are ordered by a key extracted from each element.
+///
+/// The key function is called whenever two elements must be compared, so the elements
+/// themselves don't need to implement `Ord`. Two elements with the same key are considered
+/// the same element: inserting the second one fails, while `update` replaces the first one.
+///
+/// The elements are stored inside a `SortedContainers` comparing their keys, while the lookups
+/// by key compare the key in input with the keys of the stored elements.
+pub struct SortedKeyContainers<T, K, F>
+where
+    F: Fn(&T) -> K,
+{
+    sorted: SortedContainers<T>,
+    key: Arc<F>,
+    order_type: OrderType,
+}
+impl<T, K: Ord, F: Fn(&T) -> K + Send + Sync + 'static> SortedKeyContainers<T, K, F> {
+    /// Constructs a new empty `SortedKeyContainers<T, K, F>` with the specified order type
+    /// and key function.
+    ///
+    /// # Examples
+    /// let mut people = SortedKeyContainers::new(OrderType::Asc, |person: &Person| person.age);
+    /// // the sorted collection will store the people by ascending age
+    pub fn new(order_type: OrderType, key: F) -> SortedKeyContainers<T, K, F> {
+        SortedKeyContainers::new_with_rebalance_policy(order_type, key, FixedLoadFactor::default())
+    }
+    pub fn new_with_strategies(
+        order_type: OrderType,
+        key: F,
+        expand_strategy: fn(usize, usize) -> bool,
+        shrink_strategy: fn(usize, usize) -> bool,
+    ) -> SortedKeyContainers<T, K, F> {
+        let policy = StrategyPolicy::new(
+            move |context: &RebalanceContext| expand_strategy(context.len, context.pos),
+            move |context: &RebalanceContext| shrink_strategy(context.len, context.pos),
+        );
+        SortedKeyContainers::new_with_rebalance_policy(order_type, key, policy)
+    }
+    /// Constructs a new empty `SortedKeyContainers<T, K, F>` with the specified order type
+    /// and key function, splitting and merging the sub-vectors following the policy in input.
+    /// See `SortedContainers::builder`.
+    pub fn new_with_rebalance_policy<P: RebalancePolicy + 'static>(
+        order_type: OrderType,
+        key: F,
+        policy: P,
+    ) -> SortedKeyContainers<T, K, F> {
+        let key = Arc::new(key);
+        let comparator = KeyComparator {
+            key: Arc::clone(&key),
+            order_type,
+        };
+        SortedKeyContainers {
+            sorted: SortedContainers::from_parts(Arc::new(comparator), false, Box::new(policy), 0),
+            key,
+            order_type,
+        }
+    }
+    /// Returns the number of elements in the collection, also referred as its 'length'.
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+    /// Returns `true` if the collection contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+    /// Returns the current number of sub-vectors
+    pub fn depth(&self) -> usize {
+        self.sorted.depth()
+    }
+    /// Remove all the elements inside the collection.
+    pub fn clear(&mut self) {
+        self.sorted.clear();
+    }
+    /// Search an element inside the collection comparing its key.
+    /// Complexity is O(log(M)) + O(log(N))
+    /// If an element with the same key exists the actual position is returned.
+    pub fn find(&self, element: &T) -> Option<usize> {
+        self.sorted.find(element)
+    }
+    /// Search the element with the given key inside the collection.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn find_by_key(&self, key: &K) -> Option<usize> {
+        match self.search_key(key) {
+            Ok(pos) => Some(self.sorted.index_from_tuple(pos)),
+            Err(_) => None,
+        }
+    }
+    /// Returns a reference to the element with the given key.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn get_by_key(&self, key: &K) -> Option<&T> {
+        match self.search_key(key) {
+            Ok(pos) => Some(self.sorted.element_at_position(pos)),
+            Err(_) => None,
+        }
+    }
+    /// Insert an element inside the collection.
+    ///
+    /// If no element with the same key is inside the collection, the element is inserted
+    /// and the actual position is returned, otherwise an error is returned.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N)
+    pub fn insert(&mut self, value: T) -> Result<usize, SortedContainersError<T>> {
+        self.sorted.insert(value)
+    }
+    /// Replace the element with the same key of the one in input.
+    /// Complexity is O(log(M)) + O(log(N))
+    /// If the element exists in the collection, the element will be updated returning the actual
+    /// position, otherwise an error is returned.
+    pub fn update(&mut self, value: T) -> Result<usize, SortedContainersError<T>> {
+        self.sorted.update(value)
+    }
+    /// Insert or update an element inside the collection, returning the actual position.
+    pub fn insert_or_update(&mut self, value: T) -> Result<usize, SortedContainersError<T>> {
+        self.sorted.insert_or_update(value)
+    }
+    /// Remove the element with the same key of the one in input.
+    /// Time complexity O(log(M)) + O(log(N)) + O(N)
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        self.sorted.remove(value)
+    }
+    /// Remove the element with the given key, returning it if it was present.
+    /// Time complexity O(log(M)) + O(log(N)) + O(N)
+    pub fn remove_by_key(&mut self, key: &K) -> Option<T> {
+        match self.search_key(key) {
+            Ok(pos) => Some(self.sorted.remove_at_position(pos)),
+            Err(_) => None,
+        }
+    }
+    // Returns an iterator over the collection
+    pub fn iter(&self) -> SortedContainerIter<'_, T> {
+        self.sorted.iter()
+    }
+    /// search a key inside the collection and return the actual position
+    /// or the expected position.
+    /// Time complexity O(log(M)) + O(log(N))
+    #[inline]
+    fn search_key(&self, key: &K) -> Result<(usize, usize), (usize, usize)> {
+        self.sorted
+            .search_by(|element| self.order_type.compare(&(self.key)(element), key))
+    }
+}
+impl<T, K: Ord, F: Fn(&T) -> K + Send + Sync + 'static> Index<usize>
+    for SortedKeyContainers<T, K, F>
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.sorted[index]
+    }
+}
+impl<'a, T, K: Ord, F: Fn(&T) -> K + Send + Sync + 'static> IntoIterator
+    for &'a SortedKeyContainers<T, K, F>
+{
+    type Item = &'a T;
+
+    type IntoIter = SortedContainerIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::rebalance_policy::FixedLoadFactor;
+    use crate::sorted_containers::OrderType;
+    use crate::sorted_key_containers::SortedKeyContainers;
+    use more_asserts::{assert_gt, assert_lt};
+    use rand::prelude::SliceRandom;
+    use rand::{thread_rng, Rng};
+
+    #[derive(Debug, PartialEq)]
+    struct Record {
+        id: i32,
+        name: String,
+    }
+
+    #[test]
+    fn asc_random_insertion() {
+        let vec = gen_sorted_key_containers(50_000, OrderType::Asc);
+        for i in 1..vec.len() {
+            assert_lt!(vec[i - 1].id, vec[i].id);
+        }
+        check_keys(&vec);
+        for (i, record) in vec.iter().enumerate() {
+            assert_eq!(vec.find(record), Some(i));
+        }
+    }
+    #[test]
+    fn desc_random_insertion() {
+        let vec = gen_sorted_key_containers(50_000, OrderType::Desc);
+        for i in 1..vec.len() {
+            assert_gt!(vec[i - 1].id, vec[i].id);
+        }
+        check_keys(&vec);
+        assert_eq!(vec.find_by_key(&49_999), Some(0));
+    }
+    #[test]
+    fn test_lookup_by_key() {
+        let vec = gen_sorted_key_containers(1_000, OrderType::Asc);
+        assert_eq!(vec.find_by_key(&0), Some(1_000));
+        assert_eq!(vec.find_by_key(&1_000), None);
+        assert_eq!(vec.get_by_key(&42).unwrap().name, "42");
+        assert!(vec.get_by_key(&-1_001).is_none());
+    }
+    #[test]
+    fn test_insert_and_update() {
+        let mut vec = SortedKeyContainers::new(OrderType::Asc, |record: &Record| record.id);
+        assert!(vec.update(record(42, "a")).is_err());
+        assert_eq!(vec.insert(record(42, "a")).ok(), Some(0));
+        // a record with the same key is the same element
+        assert!(vec.insert(record(42, "b")).is_err());
+        assert_eq!(vec.update(record(42, "b")).ok(), Some(0));
+        assert_eq!(vec.insert_or_update(record(7, "c")).ok(), Some(0));
+        assert_eq!(vec.insert_or_update(record(42, "d")).ok(), Some(1));
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec[1], record(42, "d"));
+    }
+    #[test]
+    fn test_remove() {
+        let mut rng = thread_rng();
+        let mut vec = gen_sorted_key_containers(10_000, OrderType::Asc);
+        while !vec.is_empty() {
+            let id = vec[rng.gen_range(0..vec.len())].id;
+            let removed = if id % 2 == 0 {
+                vec.remove_by_key(&id)
+            } else {
+                vec.remove(&record(id, ""))
+            };
+            assert_eq!(removed, Some(record(id, &id.to_string())));
+            if vec.len().is_multiple_of(100) {
+                check_keys(&vec);
+                for i in 1..vec.len() {
+                    assert_lt!(vec[i - 1].id, vec[i].id);
+                }
+            }
+        }
+        assert_eq!(vec.depth(), 0);
+        assert!(vec.remove_by_key(&0).is_none());
+    }
+
+    #[test]
+    fn test_non_clone_keys() {
+        #[derive(PartialEq, Eq, PartialOrd, Ord)]
+        struct Initial(char);
+        let mut vec = SortedKeyContainers::new_with_rebalance_policy(
+            OrderType::Desc,
+            |record: &Record| Initial(record.name.chars().next().unwrap()),
+            FixedLoadFactor::new(2),
+        );
+        for (id, name) in ["a", "c", "b", "e", "d", "f", "h", "g"].iter().enumerate() {
+            assert!(vec.insert(record(id as i32, name)).is_ok());
+        }
+        assert!(vec.insert(record(8, "apple")).is_err());
+        assert_gt!(vec.depth(), 1);
+        let names: Vec<&str> = vec.iter().map(|record| record.name.as_str()).collect();
+        assert_eq!(names, ["h", "g", "f", "e", "d", "c", "b", "a"]);
+        assert_eq!(vec.find_by_key(&Initial('e')), Some(3));
+        assert_eq!(
+            vec.remove_by_key(&Initial('h')).map(|record| record.id),
+            Some(6)
+        );
+        assert_eq!(vec[0].name, "g");
+    }
+    fn record(id: i32, name: &str) -> Record {
+        Record {
+            id,
+            name: name.to_string(),
+        }
+    }
+    fn check_keys<F>(vec: &SortedKeyContainers<Record, i32, F>)
+    where
+        F: Fn(&Record) -> i32 + Send + Sync + 'static,
+    {
+        // every element is found by its key at its own position
+        for (i, record) in vec.iter().enumerate() {
+            assert_eq!(vec.find_by_key(&record.id), Some(i));
+        }
+    }
+    fn gen_sorted_key_containers(
+        len: usize,
+        order_type: OrderType,
+    ) -> SortedKeyContainers<Record, i32, impl Fn(&Record) -> i32 + Send + Sync + 'static> {
+        let mut rng = thread_rng();
+        let mut vec = SortedKeyContainers::new(order_type, |record: &Record| record.id);
+        let mut ids: Vec<i32> = (-(len as i32)..len as i32).collect();
+        ids.shuffle(&mut rng);
+        for id in ids {
+            assert!(vec.insert(record(id, &id.to_string())).is_ok());
+        }
+        vec
+    }
+}
@@ -0,0 +1,289 @@
+use crate::sorted_containers::{OrderType, SortedContainers};
+use crate::sorted_map_iter::{Keys, SortedMapIter, Values};
+use std::cmp::Ordering;
+use std::mem;
+
+/// An entry of a `SortedMap`, ordered by its key only.
+pub(crate) struct KeyValue<K, V> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+}
+impl<K: Ord, V> PartialEq for KeyValue<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+impl<K: Ord, V> Eq for KeyValue<K, V> {}
+impl<K: Ord, V> PartialOrd for KeyValue<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<K: Ord, V> Ord for KeyValue<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// A sorted dictionary storing its entries in ascending key order.
+///
+/// The entries are stored inside a `SortedContainers` comparing their keys only, so that both
+/// key lookups and positional lookups (`get_index`, `index_of`) are performed in O(log(N)).
+/// Lookups compare the key in input with the stored keys, without building an entry.
+pub struct SortedMap<K, V> {
+    entries: SortedContainers<KeyValue<K, V>>,
+}
+impl<K: Ord, V> Default for SortedMap<K, V> {
+    fn default() -> Self {
+        SortedMap {
+            entries: SortedContainers::new(OrderType::Asc),
+        }
+    }
+}
+impl<K: Ord, V> SortedMap<K, V> {
+    /// Constructs a new empty `SortedMap<K, V>`.
+    pub fn new() -> SortedMap<K, V> {
+        SortedMap::default()
+    }
+    /// Returns the number of entries inside the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Returns `true` if the map contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Returns the current number of sub-vectors
+    pub fn depth(&self) -> usize {
+        self.entries.depth()
+    }
+    /// Remove all the entries inside the map.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    /// Insert a key-value pair inside the map.
+    ///
+    /// If the key is not present the entry is inserted and `None` is returned, otherwise the
+    /// value is updated and the old value is returned. The key itself is not updated.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N)
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search_key(&key) {
+            Ok(pos) => Some(mem::replace(
+                &mut self.entries.element_at_position_mut(pos).value,
+                value,
+            )),
+            Err(pos) => {
+                self.entries
+                    .insert_at_position(pos, KeyValue { key, value });
+                None
+            }
+        }
+    }
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match self.search_key(key) {
+            Ok(pos) => Some(&self.entries.element_at_position(pos).value),
+            Err(_) => None,
+        }
+    }
+    /// Returns a mutable reference to the value corresponding to the key.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.search_key(key) {
+            Ok(pos) => Some(&mut self.entries.element_at_position_mut(pos).value),
+            Err(_) => None,
+        }
+    }
+    /// Returns `true` if the map contains a value for the specified key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.search_key(key).is_ok()
+    }
+    /// Remove a key from the map, returning the value at the key if the key was present.
+    /// Time complexity O(log(M)) + O(log(N)) + O(N)
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+    /// Remove a key from the map, returning the stored key and value if the key was present.
+    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
+        match self.search_key(key) {
+            Ok(pos) => {
+                let KeyValue { key, value } = self.entries.remove_at_position(pos);
+                Some((key, value))
+            }
+            Err(_) => None,
+        }
+    }
+    /// Returns the entry stored at the given position.
+    /// Complexity is O(log(M))
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        if index >= self.len() {
+            return None;
+        }
+        let KeyValue { key, value } = &self.entries[index];
+        Some((key, value))
+    }
+    /// Returns the position of the given key inside the map, or `None` if the key is not present.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn index_of(&self, key: &K) -> Option<usize> {
+        match self.search_key(key) {
+            Ok(pos) => Some(self.entries.index_from_tuple(pos)),
+            Err(_) => None,
+        }
+    }
+    /// Returns the entry with the smallest key.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entries
+            .first()
+            .map(|KeyValue { key, value }| (key, value))
+    }
+    /// Returns the entry with the largest key.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.entries
+            .last()
+            .map(|KeyValue { key, value }| (key, value))
+    }
+    /// Returns an iterator over the entries of the map, sorted by key.
+    pub fn iter(&self) -> SortedMapIter<'_, K, V> {
+        SortedMapIter {
+            inner: self.entries.iter(),
+        }
+    }
+    /// Returns an iterator over the keys of the map, in sorted order.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+    /// Returns an iterator over the values of the map, sorted by key.
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+    /// search a key inside the map and return the actual position or the expected position.
+    /// Time complexity O(log(M)) + O(log(N))
+    #[inline]
+    fn search_key(&self, key: &K) -> Result<(usize, usize), (usize, usize)> {
+        self.entries.search_by(|entry| entry.key.cmp(key))
+    }
+}
+impl<'a, K: Ord, V> IntoIterator for &'a SortedMap<K, V> {
+    type Item = (&'a K, &'a V);
+
+    type IntoIter = SortedMapIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::sorted_map::SortedMap;
+    use more_asserts::assert_lt;
+    use rand::prelude::SliceRandom;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn random_insertion() {
+        let map = gen_sorted_map(100_000);
+        assert_eq!(map.len(), 200_000);
+        assert_eq!(map.depth(), map.entries.depth());
+        let mut prev_key = None;
+        for (key, value) in &map {
+            if let Some(prev) = prev_key {
+                assert_lt!(prev, *key);
+            }
+            assert_eq!(*value, key.to_string());
+            prev_key = Some(*key);
+        }
+        check_keys(&map);
+    }
+    #[test]
+    fn test_insert_returns_old_value() {
+        let mut map = SortedMap::new();
+        assert_eq!(map.insert(42, "a"), None);
+        assert_eq!(map.insert(42, "b"), Some("a"));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&42), Some(&"b"));
+        assert_eq!(map.get(&7), None);
+    }
+    #[test]
+    fn test_get_mut() {
+        let mut map = gen_sorted_map(5_000);
+        map.get_mut(&42).unwrap().push('!');
+        assert_eq!(map.get(&42).unwrap(), "42!");
+        assert!(map.get_mut(&5_000).is_none());
+    }
+    #[test]
+    fn test_positional_access() {
+        let map = gen_sorted_map(10_000);
+        for (i, key) in (-10_000..10_000).enumerate() {
+            assert_eq!(map.get_index(i), Some((&key, &key.to_string())));
+            assert_eq!(map.index_of(&key), Some(i));
+        }
+        assert_eq!(map.get_index(map.len()), None);
+        assert_eq!(map.index_of(&10_000), None);
+        assert_eq!(
+            map.first_key_value(),
+            Some((&-10_000, &"-10000".to_string()))
+        );
+        assert_eq!(map.last_key_value(), Some((&9_999, &"9999".to_string())));
+    }
+    #[test]
+    fn test_remove() {
+        let mut rng = thread_rng();
+        let mut map = gen_sorted_map(5_000);
+        while !map.is_empty() {
+            let idx = rng.gen_range(0..map.len());
+            let key = *map.get_index(idx).unwrap().0;
+            assert_eq!(map.remove(&key), Some(key.to_string()));
+            assert!(!map.contains_key(&key));
+            if map.len().is_multiple_of(100) {
+                check_keys(&map);
+            }
+        }
+        assert_eq!(map.depth(), 0);
+        assert_eq!(map.first_key_value(), None);
+        assert_eq!(map.last_key_value(), None);
+    }
+    #[test]
+    fn test_iterators() {
+        let map = gen_sorted_map(5_000);
+        for (key, c_key) in map.keys().zip(-5_000..) {
+            assert_eq!(c_key, *key);
+        }
+        for (value, c_key) in map.values().zip(-5_000..) {
+            assert_eq!(c_key.to_string(), *value);
+        }
+        assert_eq!(map.iter().count(), map.len());
+    }
+
+    #[test]
+    fn test_non_clone_keys() {
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Key(u32);
+        let mut map = SortedMap::new();
+        for i in (0..3_000).rev() {
+            assert!(map.insert(Key(i), vec![i as f64]).is_none());
+        }
+        assert_eq!(map.insert(Key(7), vec![0.5]), Some(vec![7.0]));
+        assert_eq!(map.get_index(7), Some((&Key(7), &vec![0.5])));
+        assert_eq!(
+            map.remove_entry(&Key(2_999)),
+            Some((Key(2_999), vec![2_999.0]))
+        );
+        assert_eq!(map.last_key_value().map(|(key, _)| key), Some(&Key(2_998)));
+    }
+    fn check_keys(map: &SortedMap<i32, String>) {
+        // the keys are strictly increasing and every one of them is found at its position
+        assert!(map.keys().is_sorted_by(|a, b| a < b));
+        for (i, key) in map.keys().enumerate() {
+            assert_eq!(map.index_of(key), Some(i));
+        }
+    }
+    fn gen_sorted_map(len: usize) -> SortedMap<i32, String> {
+        let mut rng = thread_rng();
+        let mut map = SortedMap::new();
+        let mut keys: Vec<i32> = (-(len as i32)..len as i32).collect();
+        keys.shuffle(&mut rng);
+        for key in keys {
+            assert!(map.insert(key, key.to_string()).is_none());
+        }
+        map
+    }
+}
@@ -0,0 +1,50 @@
+use crate::sorted_container_iter::SortedContainerIter;
+use crate::sorted_map::KeyValue;
+use std::iter::FusedIterator;
+
+pub struct SortedMapIter<'a, K, V> {
+    pub(crate) inner: SortedContainerIter<'a, KeyValue<K, V>>,
+}
+
+impl<'a, K, V> Iterator for SortedMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|KeyValue { key, value }| (key, value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<K, V> FusedIterator for SortedMapIter<'_, K, V> {}
+
+pub struct Keys<'a, K, V> {
+    pub(crate) inner: SortedMapIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V> {
+    pub(crate) inner: SortedMapIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
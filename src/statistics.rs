@@ -0,0 +1,436 @@
+use crate::sorted_containers::SortedContainers;
+
+/// Defines how a quantile falling between two elements is computed, following the methods
+/// offered by numpy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantileMethod {
+    /// the lower of the two elements
+    Lower,
+    /// the higher of the two elements
+    Higher,
+    /// the nearest of the two elements, the one with an even position if they are equally near
+    Nearest,
+    /// the average of the two elements
+    Midpoint,
+    /// the linear interpolation of the two elements
+    Linear,
+}
+
+/// Defines which of the two elements is returned when a quantile falls between them, for
+/// elements that can't be interpolated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMethod {
+    /// the lower of the two elements
+    Lower,
+    /// the higher of the two elements
+    Higher,
+    /// the nearest of the two elements, the one with an even position if they are equally near
+    Nearest,
+}
+
+/// Numeric types whose values can be interpolated to compute quantiles.
+pub trait Interpolate: Clone {
+    /// Returns the value at `fraction` (between 0 and 1) of the way from `lower` to `upper`.
+    /// Integers are rounded to the nearest value, ties to the even one, so swapping `lower` and
+    /// `upper` while replacing `fraction` with `1 - fraction` gives the same value.
+    fn interpolate(lower: &Self, upper: &Self, fraction: f64) -> Self;
+}
+/// interpolates two integers mapped to `u128` preserving their order, without overflowing
+fn interpolate_u128(lower: u128, upper: u128, fraction: f64) -> u128 {
+    // the fraction is converted to a fixed point number with 53 fractional bits
+    const ONE: u128 = 1 << 53;
+    let fraction = (fraction * ONE as f64).round() as u128;
+    // the value is computed from the lowest integer, so that it doesn't depend on their order
+    let (lowest, distance, fraction) = if lower <= upper {
+        (lower, upper - lower, fraction)
+    } else {
+        (upper, lower - upper, ONE - fraction)
+    };
+    let (high, low) = (distance >> 53, distance & (ONE - 1));
+    let (quotient, remainder) = ((low * fraction) >> 53, (low * fraction) & (ONE - 1));
+    let value = lowest + high * fraction + quotient;
+    if remainder > ONE / 2 || (remainder == ONE / 2 && value % 2 == 1) {
+        value + 1
+    } else {
+        value
+    }
+}
+macro_rules! impl_interpolate_unsigned {
+    ($($numeric:ty),*) => {
+        $(
+            impl Interpolate for $numeric {
+                fn interpolate(lower: &Self, upper: &Self, fraction: f64) -> Self {
+                    interpolate_u128(*lower as u128, *upper as u128, fraction) as $numeric
+                }
+            }
+        )*
+    };
+}
+macro_rules! impl_interpolate_signed {
+    ($($numeric:ty),*) => {
+        $(
+            impl Interpolate for $numeric {
+                fn interpolate(lower: &Self, upper: &Self, fraction: f64) -> Self {
+                    // flipping the sign bit maps the signed values to unsigned ones in order
+                    const SIGN: u128 = 1 << 127;
+                    let (lower, upper) = (*lower as i128 as u128, *upper as i128 as u128);
+                    let interpolated = interpolate_u128(lower ^ SIGN, upper ^ SIGN, fraction);
+                    (interpolated ^ SIGN) as i128 as $numeric
+                }
+            }
+        )*
+    };
+}
+impl_interpolate_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_interpolate_signed!(i8, i16, i32, i64, i128, isize);
+macro_rules! impl_interpolate_float {
+    ($($numeric:ty),*) => {
+        $(
+            impl Interpolate for $numeric {
+                fn interpolate(lower: &Self, upper: &Self, fraction: f64) -> Self {
+                    let (lower, upper) = (*lower as f64, *upper as f64);
+                    (lower + (upper - lower) * fraction) as $numeric
+                }
+            }
+        )*
+    };
+}
+impl_interpolate_float!(f32, f64);
+
+impl<T> SortedContainers<T> {
+    /// Returns the median of the collection, the average of the two middle elements if the
+    /// length is even.
+    /// Complexity is O(log(M))
+    pub fn median(&self) -> Option<T>
+    where
+        T: Interpolate,
+    {
+        self.quantile(0.5, QuantileMethod::Midpoint)
+    }
+    /// Returns the median of the collection, the lower of the two middle elements if the
+    /// length is even.
+    /// Complexity is O(log(M))
+    pub fn median_low(&self) -> Option<&T> {
+        let position = self.position_by_value(self.len().checked_sub(1)? / 2);
+        self.iter().nth(position)
+    }
+    /// Returns the median of the collection, the higher of the two middle elements if the
+    /// length is even.
+    /// Complexity is O(log(M))
+    pub fn median_high(&self) -> Option<&T> {
+        let position = self.position_by_value(self.len().checked_sub(1)?.div_ceil(2));
+        self.iter().nth(position)
+    }
+    /// Returns the quantile `q` of the collection, computed with the method in input when it
+    /// falls between two elements. Quantiles follow the value order of the elements, so the
+    /// quantile 0 is the lowest element in a descending collection too, while they follow the
+    /// order of the comparator in a collection with a custom one.
+    /// Complexity is O(log(M))
+    /// Panics if `q` is not between 0 and 1.
+    pub fn quantile(&self, q: f64, method: QuantileMethod) -> Option<T>
+    where
+        T: Interpolate,
+    {
+        self.quantiles(&[q], method).pop()
+    }
+    /// Returns the quantiles in input, in the same order. The positions of the quantiles are
+    /// visited in a single pass over the collection.
+    /// Complexity is O(K log(K)) + O(K log(M))
+    /// Panics if a quantile is not between 0 and 1.
+    pub fn quantiles(&self, qs: &[f64], method: QuantileMethod) -> Vec<T>
+    where
+        T: Interpolate,
+    {
+        self.quantiles_with(
+            qs,
+            |lower_element, higher_element, lower, fraction| match method {
+                QuantileMethod::Lower => lower_element.clone(),
+                QuantileMethod::Higher => higher_element.clone(),
+                QuantileMethod::Nearest => {
+                    if is_lower_nearest(lower, fraction) {
+                        lower_element.clone()
+                    } else {
+                        higher_element.clone()
+                    }
+                }
+                QuantileMethod::Midpoint => T::interpolate(lower_element, higher_element, 0.5),
+                QuantileMethod::Linear => T::interpolate(lower_element, higher_element, fraction),
+            },
+        )
+    }
+    /// Returns the quantile `q` of the collection, choosing one of the two elements with the
+    /// method in input when it falls between them. Unlike `quantile`, the elements don't need
+    /// to be numeric.
+    /// Complexity is O(log(M))
+    /// Panics if `q` is not between 0 and 1.
+    pub fn quantile_select(&self, q: f64, method: SelectionMethod) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.quantiles_select(&[q], method).pop()
+    }
+    /// Returns the quantiles in input, in the same order, choosing one of the two elements with
+    /// the method in input when a quantile falls between them. See `quantiles`.
+    /// Complexity is O(K log(K)) + O(K log(M))
+    /// Panics if a quantile is not between 0 and 1.
+    pub fn quantiles_select(&self, qs: &[f64], method: SelectionMethod) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.quantiles_with(qs, |lower_element, higher_element, lower, fraction| {
+            let lower_selected = match method {
+                SelectionMethod::Lower => true,
+                SelectionMethod::Higher => false,
+                SelectionMethod::Nearest => is_lower_nearest(lower, fraction),
+            };
+            if lower_selected {
+                lower_element.clone()
+            } else {
+                higher_element.clone()
+            }
+        })
+    }
+    /// computes the quantiles in input with the closure in input, receiving the two elements
+    /// surrounding the quantile, the position of the lower one and the fraction of the way
+    /// from the lower to the higher one
+    fn quantiles_with<R>(&self, qs: &[f64], quantile: impl Fn(&T, &T, usize, f64) -> R) -> Vec<R> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let positions: Vec<(usize, usize, f64)> = qs
+            .iter()
+            .map(|&q| {
+                assert!((0.0..=1.0).contains(&q), "quantile must be between 0 and 1");
+                let position = (self.len() - 1) as f64 * q;
+                let lower = position.floor() as usize;
+                (lower, position.ceil() as usize, position - lower as f64)
+            })
+            .collect();
+        // visit all the needed positions in order with a single iterator
+        let mut needed: Vec<usize> = positions
+            .iter()
+            .flat_map(|&(lower, higher, _)| [lower, higher])
+            .map(|position| self.position_by_value(position))
+            .collect();
+        needed.sort_unstable();
+        needed.dedup();
+        let mut iter = self.iter();
+        let mut next_position = 0;
+        let elements: Vec<&T> = needed
+            .iter()
+            .map(|&position| {
+                let element = iter.nth(position - next_position).unwrap();
+                next_position = position + 1;
+                element
+            })
+            .collect();
+        let element = |position: usize| {
+            elements[needed
+                .binary_search(&self.position_by_value(position))
+                .unwrap()]
+        };
+        positions
+            .into_iter()
+            .map(|(lower, higher, fraction)| {
+                quantile(element(lower), element(higher), lower, fraction)
+            })
+            .collect()
+    }
+    /// Returns the percentage of elements of the collection lower than or equal to the one in
+    /// input, in the value order of the elements like the quantiles.
+    /// Complexity is O(log(M)) + O(log(N))
+    pub fn percentile_rank(&self, value: &T) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let not_greater = if self.is_descending() {
+            self.len() - self.bisect_left(value)
+        } else {
+            self.bisect_right(value)
+        };
+        Some(not_greater as f64 * 100.0 / self.len() as f64)
+    }
+    /// returns the position in the collection of the element at the position in input in the
+    /// value order, i.e. counting from the end of a descending collection
+    fn position_by_value(&self, position: usize) -> usize {
+        if self.is_descending() {
+            self.len() - 1 - position
+        } else {
+            position
+        }
+    }
+}
+/// returns `true` if the quantile at `fraction` of the way from the element at position `lower`
+/// to the next one is nearer to the lower element, or as near as the higher one and `lower` is
+/// even
+fn is_lower_nearest(lower: usize, fraction: f64) -> bool {
+    fraction < 0.5 || (fraction == 0.5 && lower.is_multiple_of(2))
+}
+#[cfg(test)]
+mod test {
+    use crate::sorted_containers::{OrderType, SortedContainers};
+    use crate::statistics::{Interpolate, QuantileMethod, SelectionMethod};
+    use rand::prelude::SliceRandom;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_median() {
+        let mut elements: Vec<i32> = (1..=10_001).collect();
+        elements.shuffle(&mut thread_rng());
+        let mut vec: SortedContainers<i32> = elements.into_iter().collect();
+        assert_eq!(vec.median(), Some(5_001));
+        assert_eq!(vec.median_low(), Some(&5_001));
+        assert_eq!(vec.median_high(), Some(&5_001));
+        assert!(vec.insert(10_002).is_ok());
+        assert_eq!(vec.median(), Some(5_002));
+        assert_eq!(vec.median_low(), Some(&5_001));
+        assert_eq!(vec.median_high(), Some(&5_002));
+        let empty: SortedContainers<i32> = SortedContainers::default();
+        assert_eq!(empty.median(), None);
+        assert_eq!(empty.median_low(), None);
+        assert_eq!(empty.median_high(), None);
+    }
+    #[test]
+    fn test_quantile_methods() {
+        let vec: SortedContainers<i32> = [10, 20, 30, 40].into_iter().collect();
+        // the quantile 0.5 falls at position 1.5, between 20 and 30
+        assert_eq!(vec.quantile(0.5, QuantileMethod::Lower), Some(20));
+        assert_eq!(vec.quantile(0.5, QuantileMethod::Higher), Some(30));
+        assert_eq!(vec.quantile(0.5, QuantileMethod::Nearest), Some(30));
+        assert_eq!(vec.quantile(0.5, QuantileMethod::Midpoint), Some(25));
+        assert_eq!(vec.quantile(0.5, QuantileMethod::Linear), Some(25));
+        // the quantile 0.6 falls at position 1.8
+        assert_eq!(vec.quantile(0.6, QuantileMethod::Nearest), Some(30));
+        assert_eq!(vec.quantile(0.6, QuantileMethod::Linear), Some(28));
+        assert_eq!(vec.quantile(0.6, QuantileMethod::Midpoint), Some(25));
+        assert_eq!(vec.quantile(0.0, QuantileMethod::Linear), Some(10));
+        assert_eq!(vec.quantile(1.0, QuantileMethod::Linear), Some(40));
+        let desc = SortedContainers::from_sorted_vec(OrderType::Desc, vec![40, 30, 20, 10]);
+        assert_eq!(desc.quantile(0.0, QuantileMethod::Lower), Some(10));
+        assert_eq!(desc.quantile(0.5, QuantileMethod::Nearest), Some(30));
+        assert_eq!(desc.quantile(0.6, QuantileMethod::Linear), Some(28));
+    }
+    #[test]
+    fn test_statistics_desc() {
+        // the statistics of the same data don't depend on the order of the collection
+        for elements in [
+            vec![1, 2],
+            vec![2, 3],
+            (1..=100).collect(),
+            (0..1_001).collect(),
+        ] {
+            let asc = SortedContainers::from_sorted_vec(OrderType::Asc, elements.clone());
+            let desc = SortedContainers::from_sorted_vec(OrderType::Desc, elements);
+            assert_eq!(asc.median(), desc.median());
+            assert_eq!(asc.median_low(), desc.median_low());
+            assert_eq!(asc.median_high(), desc.median_high());
+            for method in [
+                QuantileMethod::Lower,
+                QuantileMethod::Nearest,
+                QuantileMethod::Linear,
+            ] {
+                assert_eq!(asc.quantile(0.99, method), desc.quantile(0.99, method));
+                assert_eq!(asc.quantile(0.25, method), desc.quantile(0.25, method));
+            }
+            assert_eq!(asc.percentile_rank(&10), desc.percentile_rank(&10));
+        }
+        let desc = SortedContainers::from_sorted_iter(OrderType::Desc, (1..=100).rev());
+        assert_eq!(desc.quantile(0.99, QuantileMethod::Lower), Some(99));
+        assert_eq!(desc.percentile_rank(&10), Some(10.0));
+        assert_eq!(desc.median_low(), Some(&50));
+        // ties are rounded to the even value
+        assert_eq!(u8::interpolate(&1, &2, 0.5), 2);
+        assert_eq!(u8::interpolate(&2, &1, 0.5), 2);
+        assert_eq!(i32::interpolate(&-3, &-2, 0.5), -2);
+        assert_eq!(i32::interpolate(&-2, &-3, 0.5), -2);
+        assert_eq!(u8::interpolate(&2, &3, 0.5), 2);
+    }
+    #[test]
+    fn test_quantiles() {
+        let vec: SortedContainers<u64> = (0..100_001).collect();
+        let qs = [0.99, 0.5, 0.0, 0.999, 0.5, 1.0, 0.25];
+        let quantiles = vec.quantiles(&qs, QuantileMethod::Linear);
+        assert_eq!(
+            quantiles,
+            [99_000, 50_000, 0, 99_900, 50_000, 100_000, 25_000]
+        );
+        for (q, quantile) in qs.iter().zip(quantiles) {
+            assert_eq!(vec.quantile(*q, QuantileMethod::Nearest), Some(quantile));
+        }
+        assert!(SortedContainers::<u64>::default()
+            .quantiles(&qs, QuantileMethod::Lower)
+            .is_empty());
+    }
+    #[test]
+    fn test_quantile_select() {
+        let vec: SortedContainers<String> =
+            ["a", "b", "c", "d"].map(String::from).into_iter().collect();
+        assert_eq!(
+            vec.quantile_select(0.5, SelectionMethod::Lower),
+            Some("b".into())
+        );
+        assert_eq!(
+            vec.quantile_select(0.5, SelectionMethod::Higher),
+            Some("c".into())
+        );
+        assert_eq!(
+            vec.quantile_select(0.5, SelectionMethod::Nearest),
+            Some("c".into())
+        );
+        assert_eq!(
+            vec.quantile_select(0.4, SelectionMethod::Nearest),
+            Some("b".into())
+        );
+        assert_eq!(
+            vec.quantiles_select(&[1.0, 0.0], SelectionMethod::Lower),
+            ["d", "a"]
+        );
+        let numbers: SortedContainers<u64> = (0..100_001).collect();
+        let qs = [0.99, 0.5, 0.0, 0.12345, 1.0];
+        assert_eq!(
+            numbers.quantiles_select(&qs, SelectionMethod::Nearest),
+            numbers.quantiles(&qs, QuantileMethod::Nearest)
+        );
+    }
+    #[test]
+    fn test_interpolate_large_integers() {
+        let vec: SortedContainers<u64> = [u64::MAX - 3, u64::MAX - 1].into_iter().collect();
+        assert_eq!(vec.median(), Some(u64::MAX - 2));
+        assert_eq!(
+            u64::interpolate(&(u64::MAX - 10), &u64::MAX, 0.3),
+            u64::MAX - 7
+        );
+        assert_eq!(u64::interpolate(&u64::MAX, &0, 0.25), u64::MAX - (1 << 62));
+        assert_eq!(i64::interpolate(&i64::MIN, &i64::MAX, 0.5), 0);
+        assert_eq!(
+            i64::interpolate(&(i64::MAX - 1), &i64::MAX, 0.5),
+            i64::MAX - 1
+        );
+        assert_eq!(
+            u128::interpolate(&(u128::MAX - 2), &u128::MAX, 0.5),
+            u128::MAX - 1
+        );
+        assert_eq!(
+            i128::interpolate(&i128::MIN, &(i128::MIN + 4), 0.75),
+            i128::MIN + 3
+        );
+        assert_eq!(i8::interpolate(&-128, &127, 1.0), 127);
+    }
+    #[test]
+    #[should_panic(expected = "quantile must be between 0 and 1")]
+    fn test_quantile_out_of_range() {
+        let vec: SortedContainers<i32> = (0..10).collect();
+        vec.quantile(1.5, QuantileMethod::Linear);
+    }
+    #[test]
+    fn test_percentile_rank() {
+        let vec: SortedContainers<i32> = (1..=200).collect();
+        assert_eq!(vec.percentile_rank(&50), Some(25.0));
+        assert_eq!(vec.percentile_rank(&0), Some(0.0));
+        assert_eq!(vec.percentile_rank(&1_000), Some(100.0));
+        let mut vec = SortedContainers::new_multiset(OrderType::Asc);
+        vec.extend([1, 2, 2, 2, 3]);
+        assert_eq!(vec.percentile_rank(&2), Some(80.0));
+        assert_eq!(SortedContainers::default().percentile_rank(&2), None);
+    }
+}
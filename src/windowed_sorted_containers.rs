@@ -0,0 +1,274 @@
+use crate::sorted_container_iter::SortedContainerIter;
+use crate::sorted_containers::{OrderType, SortedContainers};
+use crate::statistics::{Interpolate, QuantileMethod, SelectionMethod};
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Window {
+    Capacity(usize),
+    Duration(u64),
+}
+/// A sliding window over a stream of values, kept sorted to answer order statistics on the
+/// values currently in the window.
+///
+/// The window is bounded either by the number of values or by their age: the arrivals are
+/// tracked in a FIFO and the oldest ones are evicted from the sorted collection as new values
+/// are pushed. Time is supplied by the caller as a `u64` timestamp, in any unit.
+pub struct WindowedSortedContainers<T> {
+    sorted: SortedContainers<T>,
+    arrivals: VecDeque<(T, u64)>,
+    window: Window,
+}
+impl<T: Ord + Clone> WindowedSortedContainers<T> {
+    /// Constructs a new empty window holding at most `capacity` values, sorted following the
+    /// order type in input.
+    ///
+    /// # Examples
+    /// let mut window = WindowedSortedContainers::new_with_capacity(OrderType::Asc, 3);
+    /// window.push(1);
+    /// window.push(5);
+    /// window.push(3);
+    /// assert_eq!(window.push(4), Some(1));
+    /// assert_eq!(window.median(), Some(4));
+    ///
+    /// Panics if the capacity is 0.
+    pub fn new_with_capacity(order_type: OrderType, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+        WindowedSortedContainers {
+            sorted: SortedContainers::new_multiset(order_type),
+            arrivals: VecDeque::with_capacity(capacity),
+            window: Window::Capacity(capacity),
+        }
+    }
+    /// Constructs a new empty window holding the values pushed in the last `duration` time
+    /// units, sorted following the order type in input. A value pushed at `timestamp` expires
+    /// at `timestamp + duration`.
+    ///
+    /// # Examples
+    /// let mut window = WindowedSortedContainers::new_with_duration(OrderType::Asc, 60);
+    /// window.push_at(10, 0);
+    /// window.push_at(20, 30);
+    /// assert_eq!(window.push_at(30, 60), vec![10]);
+    pub fn new_with_duration(order_type: OrderType, duration: u64) -> Self {
+        WindowedSortedContainers {
+            sorted: SortedContainers::new_multiset(order_type),
+            arrivals: VecDeque::new(),
+            window: Window::Duration(duration),
+        }
+    }
+    /// Returns the maximum number of values of a capacity window, `None` for a time window.
+    pub fn capacity(&self) -> Option<usize> {
+        match self.window {
+            Window::Capacity(capacity) => Some(capacity),
+            Window::Duration(_) => None,
+        }
+    }
+    /// Returns the duration of a time window, `None` for a capacity window.
+    pub fn duration(&self) -> Option<u64> {
+        match self.window {
+            Window::Capacity(_) => None,
+            Window::Duration(duration) => Some(duration),
+        }
+    }
+    /// Push a value in the window, returning the value evicted to make room for it if the
+    /// window is full.
+    /// In a time window the value is considered arrived together with the latest pushed one,
+    /// so no value is evicted: use `push_at` to advance the time.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N)
+    pub fn push(&mut self, value: T) -> Option<T> {
+        let timestamp = self.arrivals.back().map_or(0, |(_, timestamp)| *timestamp);
+        self.push_at(value, timestamp).pop()
+    }
+    /// Push a value arrived at `timestamp` in the window, returning the evicted values in
+    /// arrival order: the values expired at `timestamp` for a time window, the oldest value if
+    /// the window is full for a capacity window.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N) for each value pushed or evicted
+    /// Panics if `timestamp` precedes the one of the latest pushed value.
+    pub fn push_at(&mut self, value: T, timestamp: u64) -> Vec<T> {
+        if let Some((_, latest)) = self.arrivals.back() {
+            assert!(
+                timestamp >= *latest,
+                "timestamp precedes the latest pushed value"
+            );
+        }
+        let mut evicted = self.evict_expired(timestamp);
+        if let Window::Capacity(capacity) = self.window {
+            if self.arrivals.len() == capacity {
+                evicted.extend(self.evict_oldest());
+            }
+        }
+        // the values are stored in a multiset, so the insertion always succeeds
+        let _ = self.sorted.insert(value.clone());
+        self.arrivals.push_back((value, timestamp));
+        evicted
+    }
+    /// Evict the values expired at `now` from a time window, returning them in arrival order.
+    /// A capacity window is left unchanged.
+    /// Complexity is O(log(M)) + O(log(N)) + O(N) for each evicted value
+    pub fn evict_expired(&mut self, now: u64) -> Vec<T> {
+        let mut evicted = Vec::new();
+        if let Window::Duration(duration) = self.window {
+            while self
+                .arrivals
+                .front()
+                .is_some_and(|(_, timestamp)| timestamp.saturating_add(duration) <= now)
+            {
+                evicted.extend(self.evict_oldest());
+            }
+        }
+        evicted
+    }
+    fn evict_oldest(&mut self) -> Option<T> {
+        let (value, _) = self.arrivals.front()?;
+        // equal values are kept in insertion order, so the first equal one is the oldest
+        let evicted = self.sorted.remove_one(value);
+        self.arrivals.pop_front();
+        evicted
+    }
+    /// Returns the number of values in the window
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+    /// Returns `true` if the window contains no values
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+    /// Remove all the values from the window
+    pub fn clear(&mut self) {
+        self.sorted.clear();
+        self.arrivals.clear();
+    }
+    /// Returns the values in the window, sorted.
+    pub fn sorted(&self) -> &SortedContainers<T> {
+        &self.sorted
+    }
+    /// Returns an iterator over the values in the window, sorted.
+    pub fn iter(&self) -> SortedContainerIter<'_, T> {
+        self.sorted.iter()
+    }
+    /// Returns an iterator over the values in the window with their timestamps, in arrival
+    /// order.
+    pub fn arrivals(&self) -> impl DoubleEndedIterator<Item = (&T, u64)> + ExactSizeIterator {
+        self.arrivals
+            .iter()
+            .map(|(value, timestamp)| (value, *timestamp))
+    }
+    /// Returns the median of the values in the window. See `SortedContainers::median`.
+    pub fn median(&self) -> Option<T>
+    where
+        T: Interpolate,
+    {
+        self.sorted.median()
+    }
+    /// Returns the quantile `q` of the values in the window. See `SortedContainers::quantile`.
+    pub fn quantile(&self, q: f64, method: QuantileMethod) -> Option<T>
+    where
+        T: Interpolate,
+    {
+        self.sorted.quantile(q, method)
+    }
+    /// Returns the quantiles in input of the values in the window.
+    /// See `SortedContainers::quantiles`.
+    pub fn quantiles(&self, qs: &[f64], method: QuantileMethod) -> Vec<T>
+    where
+        T: Interpolate,
+    {
+        self.sorted.quantiles(qs, method)
+    }
+    /// Returns the quantile `q` of the values in the window, choosing one of the two values
+    /// when it falls between them. See `SortedContainers::quantile_select`.
+    pub fn quantile_select(&self, q: f64, method: SelectionMethod) -> Option<T> {
+        self.sorted.quantile_select(q, method)
+    }
+    /// Returns the number of values in the window that precede the one in input.
+    /// See `SortedContainers::rank`.
+    pub fn rank(&self, value: &T) -> usize {
+        self.sorted.rank(value)
+    }
+    /// Returns the percentage of values in the window not following the one in input.
+    /// See `SortedContainers::percentile_rank`.
+    pub fn percentile_rank(&self, value: &T) -> Option<f64> {
+        self.sorted.percentile_rank(value)
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::sorted_containers::OrderType;
+    use crate::statistics::QuantileMethod;
+    use crate::windowed_sorted_containers::WindowedSortedContainers;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_capacity_window() {
+        let mut window = WindowedSortedContainers::new_with_capacity(OrderType::Asc, 3);
+        assert_eq!(window.push(1), None);
+        assert_eq!(window.push(5), None);
+        assert_eq!(window.push(3), None);
+        assert_eq!(window.median(), Some(3));
+        assert_eq!(window.push(4), Some(1));
+        assert_eq!(window.median(), Some(4));
+        assert_eq!(window.push(4), Some(5));
+        assert_eq!(window.push(0), Some(3));
+        assert_eq!(window.iter().copied().collect::<Vec<i32>>(), [0, 4, 4]);
+        assert_eq!(window.rank(&4), 1);
+        assert_eq!(window.len(), 3);
+        assert_eq!(window.capacity(), Some(3));
+        assert_eq!(window.duration(), None);
+        window.clear();
+        assert!(window.is_empty());
+        assert_eq!(window.push(7), None);
+    }
+    #[test]
+    fn test_rolling_median() {
+        let capacity = 501;
+        let mut window = WindowedSortedContainers::new_with_capacity(OrderType::Asc, capacity);
+        let samples: Vec<u32> = (0..20_000)
+            .map(|_| thread_rng().gen_range(0..1_000))
+            .collect();
+        for (i, sample) in samples.iter().enumerate() {
+            let evicted = window.push(*sample);
+            let start = (i + 1).saturating_sub(capacity);
+            assert_eq!(evicted, i.checked_sub(capacity).map(|old| samples[old]));
+            if i % 97 == 0 {
+                let mut expected = samples[start..=i].to_vec();
+                expected.sort();
+                assert_eq!(
+                    window.sorted().median_low(),
+                    Some(&expected[(expected.len() - 1) / 2])
+                );
+                assert_eq!(
+                    window.quantile(0.9, QuantileMethod::Lower),
+                    Some(expected[(expected.len() - 1) * 9 / 10])
+                );
+            }
+        }
+        assert_eq!(window.len(), capacity);
+    }
+    #[test]
+    fn test_time_window() {
+        let mut window = WindowedSortedContainers::new_with_duration(OrderType::Desc, 60);
+        assert!(window.push_at(10, 0).is_empty());
+        assert!(window.push_at(30, 20).is_empty());
+        assert!(window.push_at(20, 20).is_empty());
+        assert_eq!(window.push(20), None);
+        assert_eq!(
+            window.iter().copied().collect::<Vec<i32>>(),
+            [30, 20, 20, 10]
+        );
+        assert_eq!(window.push_at(40, 60), vec![10]);
+        assert_eq!(window.arrivals().next(), Some((&30, 20)));
+        assert!(window.evict_expired(79).is_empty());
+        assert_eq!(window.evict_expired(80), vec![30, 20, 20]);
+        assert_eq!(window.median(), Some(40));
+        assert_eq!(window.push_at(50, 1_000), vec![40]);
+        assert_eq!(window.percentile_rank(&50), Some(100.0));
+        assert_eq!(window.duration(), Some(60));
+    }
+    #[test]
+    #[should_panic(expected = "timestamp precedes the latest pushed value")]
+    fn test_time_window_out_of_order() {
+        let mut window = WindowedSortedContainers::new_with_duration(OrderType::Asc, 60);
+        window.push_at(1, 10);
+        window.push_at(2, 5);
+    }
+}